@@ -3,7 +3,14 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use craboken::msg::{BalanceResponse, HandleMsg, InitMsg, QueryMsg};
+use craboken::msg::{
+    AdminResponse, AllAccountsResponse, AllAllowancesResponse, AllowanceResponse,
+    AllowancesBySpenderResponse, BalanceAndSupplyResponse, BalanceResponse, ConfigResponse,
+    DownloadLogoResponse, FormattedBalanceResponse, HandleMsg, InitMsg, IsFrozenResponse,
+    MarketingInfoResponse, MigrateMsg, MinTransferAmountResponse, MinterResponse, QueryMsg,
+    ReceiverHandleMsg, TokenInfoResponse, TopHoldersResponse, TransferHookMsg, VersionResponse,
+    VestedAmountResponse,
+};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -14,5 +21,27 @@ fn main() {
     export_schema(&schema_for!(InitMsg), &out_dir);
     export_schema(&schema_for!(HandleMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(ReceiverHandleMsg), &out_dir);
+    export_schema(&schema_for!(TransferHookMsg), &out_dir);
     export_schema(&schema_for!(BalanceResponse), &out_dir);
+    export_schema(&schema_for!(BalanceAndSupplyResponse), &out_dir);
+    export_schema(&schema_for!(FormattedBalanceResponse), &out_dir);
+    export_schema(&schema_for!(AllowanceResponse), &out_dir);
+    export_schema(&schema_for!(TokenInfoResponse), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(MinterResponse), &out_dir);
+    export_schema(&schema_for!(AdminResponse), &out_dir);
+    export_schema(&schema_for!(MinTransferAmountResponse), &out_dir);
+    export_schema(&schema_for!(AllAccountsResponse), &out_dir);
+    export_schema(&schema_for!(TopHoldersResponse), &out_dir);
+    export_schema(&schema_for!(AllAllowancesResponse), &out_dir);
+    export_schema(&schema_for!(AllowancesBySpenderResponse), &out_dir);
+    export_schema(&schema_for!(MarketingInfoResponse), &out_dir);
+    export_schema(&schema_for!(DownloadLogoResponse), &out_dir);
+    export_schema(&schema_for!(IsFrozenResponse), &out_dir);
+    export_schema(&schema_for!(VersionResponse), &out_dir);
+    export_schema(&schema_for!(VestedAmountResponse), &out_dir);
+    #[cfg(feature = "debug")]
+    export_schema(&schema_for!(craboken::msg::RawStateResponse), &out_dir);
 }
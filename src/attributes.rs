@@ -0,0 +1,9 @@
+// Log attribute keys shared with the CW20 spec, which explorers match on exactly.
+
+pub const ACTION: &str = "action";
+pub const FROM: &str = "from";
+pub const TO: &str = "to";
+pub const BY: &str = "by";
+pub const AMOUNT: &str = "amount";
+pub const OWNER: &str = "owner";
+pub const SPENDER: &str = "spender";
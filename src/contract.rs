@@ -1,23 +1,160 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
 use cosmwasm_std::{
-    debug_print, to_binary, Api, Binary, CanonicalAddr, Env, Extern, HandleResponse, HumanAddr,
-    InitResponse, Querier, StdError, StdResult, Storage, Uint128,
+    debug_print, log, to_binary, Api, BankMsg, Binary, BlockInfo, CanonicalAddr, CosmosMsg, Env,
+    Extern, HandleResponse, HumanAddr, InitResponse, MigrateResponse, Querier, StdError, StdResult,
+    Storage, Uint128, WasmMsg,
+};
+
+use crate::attributes::{ACTION, AMOUNT, BY, FROM, OWNER, SPENDER, TO};
+use crate::error::ContractError;
+use crate::math::{add_amount, add_uint128, debit, mul_div, sub_amount, sub_uint128};
+use crate::msg::{
+    AdminResponse, AllAccountsResponse, AllAllowancesResponse, AllowanceAction, AllowanceInfo,
+    AllowanceResponse, AllowancesBySpenderResponse, BalanceAndSupplyResponse, BalanceResponse,
+    ConfigResponse, DownloadLogoResponse, Expiration, FormattedBalanceResponse, HandleMsg,
+    HolderBalance, InitBalance, InitMsg, IsFrozenResponse, Logo, LogoInfo, MarketingInfoResponse,
+    MigrateMsg, MinTransferAmountResponse, MintAction, MinterResponse, QueryMsg, ReceiverHandleMsg,
+    Rounding, SpenderAllowanceInfo, TokenInfoResponse, TokenMetadata, TopHoldersResponse,
+    TransferAction, TransferHookMsg, VersionResponse, VestedAmountResponse,
+};
+use crate::state::{
+    holder_count, prune_empty_balances, rebuild_holder_count, rebuild_spender_index, Allowance,
+    Allowances, Balances, BaselineState, Checkpoints, ContractVersion, FrozenAccounts, Hook,
+    LastReceived, MarketingInfo, MintNonces, Minters, Outflow, Outflows, ReadOnlyAllowances,
+    ReadOnlyBalances, ReadOnlyFrozenAccounts, ReadOnlyLastReceived, ReadOnlyMintNonces,
+    ReadOnlyMinters, ReadOnlyOutflows, ReadOnlySpenderIndex, ReadOnlyVestings, State,
+    TransferHooks, Vesting, Vestings,
 };
 
-use crate::msg::{BalanceResponse, HandleMsg, InitMsg, QueryMsg};
-use crate::state::{Allowance, Allowances, Balances, ReadOnlyBalances, State};
+const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const MAX_BATCH_TRANSFERS: usize = 256;
+const DEFAULT_LIMIT: u32 = 30;
+const MAX_LIMIT: u32 = 100;
+const MAX_FEE_BPS: u16 = 1000;
+const MAX_BALANCES_QUERY: usize = 50;
+const MAX_MEMO_LEN: usize = 256;
+const MAX_TRANSFER_HOOKS: usize = 10;
+const DAY_SECONDS: u64 = 24 * 60 * 60;
+const MAX_LOGO_SIZE: usize = 5 * 1024;
+const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n";
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     msg: InitMsg,
 ) -> StdResult<InitResponse> {
+    msg.validate()?;
+
+    let fee_bps = msg.fee_bps.unwrap_or(0);
+    ensure_valid_fee(fee_bps)?;
+    let fee_collector = match msg.fee_collector {
+        Some(fee_collector) => fee_collector,
+        None => msg.minter.clone(),
+    };
+    deps.api.canonical_address(&fee_collector)?;
+
+    let admin = match msg.admin {
+        Some(admin) => admin,
+        None => msg.minter.clone(),
+    };
+    deps.api.canonical_address(&admin)?;
+
+    let mut initial_balances = msg.initial_balances;
+    let declared_total_supply = match msg.init_recipient {
+        Some(address) => {
+            let amount = msg.total_supply.unwrap_or(Uint128(0));
+            if amount.u128() == 0 {
+                return Err(StdError::generic_err(
+                    "init_recipient requires a non-zero total_supply",
+                ));
+            }
+            initial_balances.push(InitBalance { address, amount });
+            None
+        }
+        None => msg.total_supply,
+    };
+
+    let mut holders: Vec<CanonicalAddr> = Vec::with_capacity(initial_balances.len());
+    let mut total_supply: u128 = 0;
+    let mut balances = Balances::new(&mut deps.storage);
+    for InitBalance { address, amount } in initial_balances {
+        let addr = deps.api.canonical_address(&address)?;
+        if holders.contains(&addr) {
+            return Err(StdError::generic_err(format!(
+                "Duplicate address in initial balances: {}",
+                address
+            )));
+        }
+
+        total_supply = total_supply
+            .checked_add(amount.u128())
+            .ok_or_else(|| StdError::generic_err("total supply overflow"))?;
+        balances.set(&addr, amount.u128())?;
+        holders.push(addr);
+    }
+    let total_supply = Uint128(total_supply);
+
+    if let Some(declared) = declared_total_supply {
+        if declared.u128() != 0 && declared != total_supply {
+            return Err(StdError::generic_err(
+                "declared total supply does not match initial balances",
+            ));
+        }
+    }
+
+    if let Some(cap) = msg.cap {
+        if total_supply > cap {
+            return Err(StdError::generic_err("Initial supply exceeds cap"));
+        }
+    }
+
     let state = State {
-        minter: msg.minter,
-        total_supply: msg.total_supply,
+        name: msg.name,
+        symbol: msg.symbol,
+        decimals: msg.decimals,
+        total_supply,
+        cap: msg.cap,
+        paused: false,
+        fee_bps,
+        fee_rounding: msg.fee_rounding,
+        fee_collector,
+        admin,
+        max_mint_per_tx: msg.max_mint_per_tx,
+        min_transfer_amount: msg.min_transfer_amount,
+        burnable_by_holders: msg.burnable_by_holders.unwrap_or(true),
+        default_allowance_ttl: msg.default_allowance_ttl,
+        daily_transfer_limit: None,
+        cooldown_blocks: None,
+        burn_address: burn_address(&deps.api, &env)?,
+        burn_fee: msg.burn_fee,
     };
 
     State::write(&mut deps.storage).save(&state)?;
 
+    let minter_addr = deps.api.canonical_address(&msg.minter)?;
+    Minters::new(&mut deps.storage).add(&minter_addr)?;
+
+    let mut frozen = FrozenAccounts::new(&mut deps.storage);
+    for address in &msg.initial_frozen {
+        frozen.add(&deps.api.canonical_address(address)?)?;
+    }
+
+    let marketing = MarketingInfo {
+        marketing: msg.marketing,
+        ..MarketingInfo::default()
+    };
+    MarketingInfo::write(&mut deps.storage).save(&marketing)?;
+
+    let version = ContractVersion {
+        contract: CONTRACT_NAME.to_string(),
+        version: CONTRACT_VERSION.to_string(),
+    };
+    ContractVersion::write(&mut deps.storage).save(&version)?;
+
     debug_print!("Contract was initialized by {}", env.message.sender);
 
     Ok(InitResponse::default())
@@ -28,19 +165,132 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     env: Env,
     msg: HandleMsg,
 ) -> StdResult<HandleResponse> {
+    match msg {
+        HandleMsg::Transfer { .. }
+        | HandleMsg::BatchTransfer { .. }
+        | HandleMsg::TransferAll { .. }
+        | HandleMsg::TransferWithMemo { .. }
+        | HandleMsg::TransferToExisting { .. }
+        | HandleMsg::TransferEnsureBalance { .. }
+        | HandleMsg::TransferFrom { .. }
+        | HandleMsg::TransferFromPartial { .. }
+        | HandleMsg::Burn { .. }
+        | HandleMsg::BurnAll {}
+        | HandleMsg::SendToBurnAddress { .. }
+        | HandleMsg::Send { .. }
+        | HandleMsg::BurnFrom { .. } => ensure_not_paused(&deps.storage)?,
+        _ => {}
+    }
+
     match msg {
         HandleMsg::Transfer { to, amount } => try_transfer(deps, env, to, amount),
+        HandleMsg::BatchTransfer { transfers } => try_batch_transfer(deps, env, transfers),
+        HandleMsg::TransferAll { to } => try_transfer_all(deps, env, to),
+        HandleMsg::TransferWithMemo { to, amount, memo } => {
+            try_transfer_with_memo(deps, env, to, amount, memo)
+        }
+        HandleMsg::TransferToExisting { to, amount } => {
+            try_transfer_to_existing(deps, env, to, amount)
+        }
+        HandleMsg::TransferEnsureBalance {
+            to,
+            amount,
+            min_resulting_balance,
+        } => try_transfer_ensure_balance(deps, env, to, amount, min_resulting_balance),
         HandleMsg::Burn { amount } => try_burn(deps, env, amount),
+        HandleMsg::BurnAll {} => try_burn_all(deps, env),
+        HandleMsg::SendToBurnAddress { amount } => try_send_to_burn_address(deps, env, amount),
+        HandleMsg::Send {
+            contract,
+            code_hash,
+            amount,
+            msg,
+        } => try_send(deps, env, contract, code_hash, amount, msg),
         HandleMsg::SetAllowance {
             spender,
             amount,
             is_allowed,
-        } => try_set_allowance(deps, env, spender, amount, is_allowed),
+            expires,
+        } => try_set_allowance(deps, env, spender, amount, is_allowed, expires),
+        HandleMsg::BatchSetAllowance { allowances } => {
+            try_batch_set_allowance(deps, env, allowances)
+        }
+        HandleMsg::SetRecurringAllowance {
+            spender,
+            amount,
+            period,
+        } => try_set_recurring_allowance(deps, env, spender, amount, period),
+        HandleMsg::IncreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => try_increase_allowance(deps, env, spender, amount, expires),
+        HandleMsg::DecreaseAllowance { spender, amount } => {
+            try_decrease_allowance(deps, env, spender, amount)
+        }
+        HandleMsg::RevokeAllowance { spender } => try_revoke_allowance(deps, env, spender),
+        HandleMsg::PruneAllowance { owner, spender } => {
+            try_prune_allowance(deps, env, owner, spender)
+        }
         HandleMsg::TransferFrom { from, to, amount } => {
             try_transfer_from(deps, env, from, to, amount)
         }
+        HandleMsg::TransferFromPartial { from, to, amount } => {
+            try_transfer_from_partial(deps, env, from, to, amount)
+        }
         HandleMsg::BurnFrom { from, amount } => try_burn_from(deps, env, from, amount),
         HandleMsg::Mint { recipient, amount } => try_mint(deps, env, recipient, amount),
+        HandleMsg::BatchMint { mints } => try_batch_mint(deps, env, mints),
+        HandleMsg::DistributeProRata { total } => try_distribute_pro_rata(deps, env, total),
+        HandleMsg::MintOnce {
+            recipient,
+            amount,
+            nonce,
+        } => try_mint_once(deps, env, recipient, amount, nonce),
+        HandleMsg::SetPaused { paused } => try_set_paused(deps, env, paused),
+        HandleMsg::UpdateMinter { new_minter } => try_update_minter(deps, env, new_minter),
+        HandleMsg::AddMinter { minter } => try_add_minter(deps, env, minter),
+        HandleMsg::RemoveMinter { minter } => try_remove_minter(deps, env, minter),
+        HandleMsg::UpdateMarketing {
+            project,
+            description,
+            marketing,
+        } => try_update_marketing(deps, env, project, description, marketing),
+        HandleMsg::UploadLogo { logo } => try_upload_logo(deps, env, logo),
+        HandleMsg::UpdateAdmin { new_admin } => try_update_admin(deps, env, new_admin),
+        HandleMsg::UpdateFee {
+            fee_bps,
+            fee_collector,
+            fee_rounding,
+        } => try_update_fee(deps, env, fee_bps, fee_collector, fee_rounding),
+        HandleMsg::UpdateMintLimit { max_mint_per_tx } => {
+            try_update_mint_limit(deps, env, max_mint_per_tx)
+        }
+        HandleMsg::UpdateDailyLimit {
+            daily_transfer_limit,
+        } => try_update_daily_limit(deps, env, daily_transfer_limit),
+        HandleMsg::UpdateCooldown { cooldown_blocks } => {
+            try_update_cooldown(deps, env, cooldown_blocks)
+        }
+        HandleMsg::Freeze { address } => try_freeze(deps, env, address),
+        HandleMsg::Unfreeze { address } => try_unfreeze(deps, env, address),
+        HandleMsg::ForceTransfer { from, to, amount } => {
+            try_force_transfer(deps, env, from, to, amount)
+        }
+        HandleMsg::Snapshot {} => try_snapshot(deps, env),
+        HandleMsg::Sweep { denom, recipient } => try_sweep(deps, env, denom, recipient),
+        HandleMsg::AddHook {
+            contract,
+            code_hash,
+        } => try_add_hook(deps, env, contract, code_hash),
+        HandleMsg::RemoveHook { contract } => try_remove_hook(deps, env, contract),
+        HandleMsg::SetVesting {
+            address,
+            locked,
+            start,
+            cliff,
+            duration,
+        } => try_set_vesting(deps, env, address, locked, start, cliff, duration),
     }
 }
 
@@ -50,643 +300,7994 @@ fn try_transfer<S: Storage, A: Api, Q: Querier>(
     to: HumanAddr,
     amount: Uint128,
 ) -> StdResult<HandleResponse> {
+    ensure_nonzero(amount)?;
+    ensure_not_token_contract(&env, &to)?;
+
     let sender_addr = deps.api.canonical_address(&env.message.sender)?;
-    let to = deps.api.canonical_address(&to)?;
-    try_transfer_inner(deps, sender_addr, to, amount)?;
-    Ok(HandleResponse::default())
+    let to_addr = deps.api.canonical_address(&to)?;
+
+    let mut messages = vec![];
+    let TransferOutcome {
+        from_balance,
+        to_balance,
+        fee,
+        burn_fee,
+    } = try_transfer_charged(
+        deps,
+        &env.block,
+        sender_addr,
+        to_addr,
+        amount,
+        &mut messages,
+    )?;
+
+    let mut logs = vec![
+        log(ACTION, "transfer"),
+        log(FROM, env.message.sender),
+        log(TO, to),
+        log(AMOUNT, amount),
+        log("from_balance", from_balance),
+        log("to_balance", to_balance),
+    ];
+    if fee > 0 {
+        logs.push(log("fee", fee));
+    }
+    if burn_fee > 0 {
+        logs.push(log("burn_fee", burn_fee));
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
 }
 
-fn try_burn<S: Storage, A: Api, Q: Querier>(
+fn try_transfer_with_memo<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
+    to: HumanAddr,
     amount: Uint128,
+    memo: String,
 ) -> StdResult<HandleResponse> {
-    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
-    try_burn_inner(deps, sender_addr, amount)?;
-    Ok(HandleResponse::default())
+    if memo.len() > MAX_MEMO_LEN {
+        return Err(StdError::generic_err(format!(
+            "Memo must not exceed {} bytes",
+            MAX_MEMO_LEN
+        )));
+    }
+
+    let mut resp = try_transfer(deps, env, to, amount)?;
+    resp.log.push(log("memo", memo));
+    Ok(resp)
 }
 
-fn try_set_allowance<S: Storage, A: Api, Q: Querier>(
+fn try_transfer_to_existing<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    spender: HumanAddr,
+    to: HumanAddr,
     amount: Uint128,
-    is_allowed: bool,
 ) -> StdResult<HandleResponse> {
-    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
-    let spender = deps.api.canonical_address(&spender)?;
+    let to_addr = deps.api.canonical_address(&to)?;
+    if !ReadOnlyBalances::new(&deps.storage).exists(&to_addr) {
+        return Err(StdError::generic_err("Recipient account does not exist"));
+    }
 
-    let mut allowances = Allowances::new(&sender_addr, &mut deps.storage);
-    allowances.set(&spender, Allowance { is_allowed, amount })?;
-    Ok(HandleResponse::default())
+    try_transfer(deps, env, to, amount)
 }
 
-fn try_transfer_from<S: Storage, A: Api, Q: Querier>(
+fn try_transfer_ensure_balance<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    from: HumanAddr,
     to: HumanAddr,
     amount: Uint128,
+    Uint128(min_resulting_balance): Uint128,
+) -> StdResult<HandleResponse> {
+    let to_addr = deps.api.canonical_address(&to)?;
+    let res = try_transfer(deps, env, to, amount)?;
+
+    // erroring here discards the transfer along with the rest of the transaction
+    let balance = ReadOnlyBalances::new(&deps.storage).get(&to_addr)?;
+    if balance < min_resulting_balance {
+        return Err(StdError::generic_err(format!(
+            "Recipient balance {} is below the required {}",
+            balance, min_resulting_balance
+        )));
+    }
+    Ok(res)
+}
+
+fn try_batch_transfer<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    transfers: Vec<TransferAction>,
 ) -> StdResult<HandleResponse> {
+    if transfers.len() > MAX_BATCH_TRANSFERS {
+        return Err(StdError::generic_err(format!(
+            "Batch must not contain more than {} transfers",
+            MAX_BATCH_TRANSFERS
+        )));
+    }
+
     let sender_addr = deps.api.canonical_address(&env.message.sender)?;
-    let from = deps.api.canonical_address(&from)?;
-    let to = deps.api.canonical_address(&to)?;
+    let state = State::read(&deps.storage).load()?;
 
-    process_allowance(&mut deps.storage, &from, &sender_addr, amount)?;
+    let mut actions = Vec::with_capacity(transfers.len());
+    let mut total = 0u128;
+    for TransferAction { to, amount } in transfers {
+        ensure_nonzero(amount)?;
+        ensure_min_transfer(&state, amount)?;
+        ensure_not_token_contract(&env, &to)?;
+        total = add_amount(total, amount.u128())?;
+        actions.push((deps.api.canonical_address(&to)?, amount));
+    }
 
-    try_transfer_inner(deps, from, to, amount)?;
+    // check the whole batch up front so a failing entry can't leave earlier ones applied
+    let burn_fees = state.burn_fee.u128().saturating_mul(actions.len() as u128);
+    let sender_balance = ReadOnlyBalances::new(&deps.storage).get(&sender_addr)?;
+    debit(sender_balance, add_amount(total, burn_fees)?)?;
+
+    let mut messages = vec![];
+    let mut fees = 0u128;
+    for (to_addr, amount) in actions {
+        let outcome = try_transfer_charged(
+            deps,
+            &env.block,
+            sender_addr.clone(),
+            to_addr,
+            amount,
+            &mut messages,
+        )?;
+        fees = add_amount(fees, outcome.fee)?;
+    }
 
-    Ok(HandleResponse::default())
+    let mut logs = vec![
+        log(ACTION, "batch_transfer"),
+        log(FROM, env.message.sender),
+        log(AMOUNT, total),
+    ];
+    if fees > 0 {
+        logs.push(log("fee", fees));
+    }
+    if burn_fees > 0 {
+        logs.push(log("burn_fee", burn_fees));
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
 }
 
-fn try_burn_from<S: Storage, A: Api, Q: Querier>(
+fn try_transfer_all<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    from: HumanAddr,
-    amount: Uint128,
+    to: HumanAddr,
 ) -> StdResult<HandleResponse> {
+    ensure_not_token_contract(&env, &to)?;
+
     let sender_addr = deps.api.canonical_address(&env.message.sender)?;
-    let from = deps.api.canonical_address(&from)?;
+    let to_addr = deps.api.canonical_address(&to)?;
+    // whatever the burn fee doesn't take is sent
+    let balance = ReadOnlyBalances::new(&deps.storage).get(&sender_addr)?;
+    let burn_fee = State::read(&deps.storage).load()?.burn_fee.u128();
+    let amount = Uint128(balance.saturating_sub(burn_fee));
+    ensure_nonzero(amount)?;
+
+    let mut messages = vec![];
+    let outcome = try_transfer_charged(
+        deps,
+        &env.block,
+        sender_addr,
+        to_addr,
+        amount,
+        &mut messages,
+    )?;
+
+    let mut logs = vec![
+        log(ACTION, "transfer_all"),
+        log(FROM, env.message.sender),
+        log(TO, to),
+        log(AMOUNT, amount),
+    ];
+    if outcome.fee > 0 {
+        logs.push(log("fee", outcome.fee));
+    }
+    if outcome.burn_fee > 0 {
+        logs.push(log("burn_fee", outcome.burn_fee));
+    }
 
-    process_allowance(&mut deps.storage, &from, &sender_addr, amount)?;
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
 
-    try_burn_inner(deps, from, amount)?;
+fn try_burn<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint128,
+) -> StdResult<HandleResponse> {
+    ensure_nonzero(amount)?;
+    ensure_can_burn(deps, &env.message.sender)?;
 
-    Ok(HandleResponse::default())
+    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
+    try_burn_inner(deps, sender_addr, amount)?;
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log(ACTION, "burn"),
+            log(FROM, env.message.sender),
+            log(AMOUNT, amount),
+        ],
+        data: None,
+    })
 }
 
-fn try_mint<S: Storage, A: Api, Q: Querier>(
+fn try_burn_all<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    recipient: HumanAddr,
-    Uint128(amount): Uint128,
 ) -> StdResult<HandleResponse> {
-    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
-    let recipient = deps.api.canonical_address(&recipient)?;
+    ensure_can_burn(deps, &env.message.sender)?;
 
-    let state = State::read(&deps.storage).load()?;
-    let minter = deps.api.canonical_address(&state.minter)?;
+    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
+    let amount = Uint128(ReadOnlyBalances::new(&deps.storage).get(&sender_addr)?);
+    ensure_nonzero(amount)?;
 
-    if minter != sender_addr {
-        return Err(StdError::unauthorized());
-    }
+    try_burn_inner(deps, sender_addr, amount)?;
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log(ACTION, "burn"),
+            log(FROM, env.message.sender),
+            log(AMOUNT, amount),
+        ],
+        data: None,
+    })
+}
 
-    let mut balances = Balances::new(&mut deps.storage);
-    let recipient_balance = balances.get(&recipient)?;
-    let new_recipient_balance = recipient_balance
-        .checked_add(amount)
-        .ok_or_else(|| StdError::generic_err("Too many tokens to mint for user"))?;
-    balances.set(&recipient, new_recipient_balance)?;
+fn try_send_to_burn_address<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint128,
+) -> StdResult<HandleResponse> {
+    ensure_nonzero(amount)?;
 
-    State::write(&mut deps.storage).update(|mut state| {
-        state.total_supply = state
-            .total_supply
-            .u128()
-            .checked_add(amount)
-            .map(Uint128)
-            .ok_or_else(|| {
-                StdError::generic_err(
-                    "More token are tried to create than available in total supply",
-                )
-            })?;
-        Ok(state)
-    })?;
+    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
+    let burn_address = State::read(&deps.storage).load()?.burn_address;
+    let mut messages = vec![];
+    // parking tokens is an ordinary transfer, so it pays the same fees
+    let outcome = try_transfer_charged(
+        deps,
+        &env.block,
+        sender_addr,
+        burn_address,
+        amount,
+        &mut messages,
+    )?;
+
+    let mut logs = vec![
+        log(ACTION, "send_to_burn_address"),
+        log(FROM, env.message.sender),
+        log(AMOUNT, amount),
+    ];
+    if outcome.fee > 0 {
+        logs.push(log("fee", outcome.fee));
+    }
+    if outcome.burn_fee > 0 {
+        logs.push(log("burn_fee", outcome.burn_fee));
+    }
 
-    Ok(HandleResponse::default())
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
 }
 
-fn try_transfer_inner<S: Storage, A: Api, Q: Querier>(
+fn try_send<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
-    from: CanonicalAddr,
-    to: CanonicalAddr,
-    Uint128(amount): Uint128,
-) -> StdResult<()> {
-    let mut balances = Balances::new(&mut deps.storage);
+    env: Env,
+    contract: HumanAddr,
+    code_hash: String,
+    amount: Uint128,
+    msg: Binary,
+) -> StdResult<HandleResponse> {
+    ensure_nonzero(amount)?;
+    ensure_not_token_contract(&env, &contract)?;
 
-    let sender_balance = balances.get(&from)?;
-    let sender_new_balance = sender_balance
-        .checked_sub(amount)
-        .ok_or_else(|| StdError::generic_err("Too many tokens to transfer"))?;
+    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
+    let contract_addr = deps.api.canonical_address(&contract)?;
+    let mut messages = vec![];
+    let outcome = try_transfer_charged(
+        deps,
+        &env.block,
+        sender_addr,
+        contract_addr,
+        amount,
+        &mut messages,
+    )?;
+
+    // no reentrancy guard is needed here: the Receive callback is a wasm message, which
+    // only runs after this handler has returned and its writes are committed, so the
+    // receiver can never observe a half-applied send. It is told what actually arrived,
+    // net of the transfer fee.
+    let receive_msg = ReceiverHandleMsg::Receive {
+        sender: env.message.sender.clone(),
+        amount: Uint128(amount.u128() - outcome.fee),
+        msg,
+    };
+    messages.push(
+        WasmMsg::Execute {
+            contract_addr: contract.clone(),
+            callback_code_hash: code_hash,
+            msg: to_binary(&receive_msg)?,
+            send: vec![],
+        }
+        .into(),
+    );
+
+    let mut logs = vec![
+        log(ACTION, "send"),
+        log(FROM, env.message.sender),
+        log(TO, contract),
+        log(AMOUNT, amount),
+    ];
+    if outcome.fee > 0 {
+        logs.push(log("fee", outcome.fee));
+    }
+    if outcome.burn_fee > 0 {
+        logs.push(log("burn_fee", outcome.burn_fee));
+    }
 
-    let to_balance = balances.get(&to)?;
-    let recipient_new_balance = to_balance
-        .checked_add(amount)
-        .ok_or_else(|| StdError::generic_err("Too many tokens to receive"))?;
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
 
-    balances.set(&from, sender_new_balance)?;
-    balances.set(&to, recipient_new_balance)?;
+fn try_set_allowance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    spender: HumanAddr,
+    amount: Uint128,
+    is_allowed: bool,
+    expires: Option<Expiration>,
+) -> StdResult<HandleResponse> {
+    let spender_addr = deps.api.canonical_address(&spender)?;
+    let expires = match expires {
+        Some(expires) => Some(expires),
+        None => default_expiration(&deps.storage, &env.block)?,
+    };
 
-    Ok(())
+    let mut allowances = Allowances::for_owner(&deps.api, &env.message.sender, &mut deps.storage)?;
+    ensure_not_self_allowance(allowances.owner(), &spender_addr)?;
+    allowances.set(
+        &spender_addr,
+        Allowance {
+            is_allowed,
+            amount,
+            expires: expires.unwrap_or_default(),
+            unlimited: amount.u128() == u128::MAX,
+            ..Allowance::default()
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log(ACTION, "set_allowance"),
+            log(OWNER, env.message.sender),
+            log(SPENDER, spender),
+            log(AMOUNT, amount),
+            log("is_allowed", is_allowed),
+        ],
+        data: None,
+    })
 }
 
-fn try_burn_inner<S: Storage, A: Api, Q: Querier>(
+fn try_batch_set_allowance<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
-    from: CanonicalAddr,
-    Uint128(amount): Uint128,
-) -> StdResult<()> {
-    let mut balances = Balances::new(&mut deps.storage);
+    env: Env,
+    actions: Vec<AllowanceAction>,
+) -> StdResult<HandleResponse> {
+    if actions.len() > MAX_BATCH_TRANSFERS {
+        return Err(StdError::generic_err(format!(
+            "Batch must not contain more than {} allowances",
+            MAX_BATCH_TRANSFERS
+        )));
+    }
 
-    let sender_balance = balances.get(&from)?;
-    let sender_new_balance = sender_balance
-        .checked_sub(amount)
-        .ok_or_else(|| StdError::generic_err("Too many tokens to burn"))?;
-    balances.set(&from, sender_new_balance)?;
+    let owner = deps.api.canonical_address(&env.message.sender)?;
+    let expires = default_expiration(&deps.storage, &env.block)?.unwrap_or_default();
+
+    // validate the whole batch before writing, so a bad entry leaves nothing applied
+    let mut spenders: Vec<CanonicalAddr> = Vec::with_capacity(actions.len());
+    for action in &actions {
+        let spender = deps.api.canonical_address(&action.spender)?;
+        ensure_not_self_allowance(&owner, &spender)?;
+        if spenders.contains(&spender) {
+            return Err(StdError::generic_err(format!(
+                "Duplicate spender in batch: {}",
+                action.spender
+            )));
+        }
+        spenders.push(spender);
+    }
 
-    State::write(&mut deps.storage).update(|mut state| {
-        state.total_supply = state
-            .total_supply
-            .u128()
-            .checked_sub(amount)
-            .map(Uint128)
-            .ok_or_else(|| {
-                StdError::generic_err(
-                    "More tokens are tried to burn than available in total supply",
-                )
-            })?;
-        Ok(state)
-    })?;
+    // each entry is logged the way a single SetAllowance logs it
+    let mut logs = vec![
+        log(ACTION, "batch_set_allowance"),
+        log(OWNER, &env.message.sender),
+    ];
+    let mut allowances = Allowances::new(&owner, &mut deps.storage);
+    for (spender, action) in spenders.iter().zip(actions) {
+        logs.push(log(SPENDER, &action.spender));
+        logs.push(log(AMOUNT, action.amount));
+        logs.push(log("is_allowed", action.is_allowed));
+        allowances.set(
+            spender,
+            Allowance {
+                is_allowed: action.is_allowed,
+                amount: action.amount,
+                expires: expires.clone(),
+                unlimited: action.amount.u128() == u128::MAX,
+                ..Allowance::default()
+            },
+        )?;
+    }
 
-    Ok(())
+    Ok(HandleResponse {
+        messages: vec![],
+        log: logs,
+        data: None,
+    })
 }
 
-fn process_allowance<S: Storage>(
-    storage: &mut S,
-    owner_addr: &CanonicalAddr,
-    allowed_addr: &CanonicalAddr,
+fn try_set_recurring_allowance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    spender: HumanAddr,
     amount: Uint128,
-) -> StdResult<()> {
-    let mut allowances = Allowances::new(owner_addr, storage);
-    let mut allowance = allowances
-        .get(allowed_addr)?
-        .filter(|allowance| allowance.is_allowed)
-        .ok_or_else(StdError::unauthorized)?;
-
-    allowance.amount = allowance
-        .amount
-        .u128()
-        .checked_sub(amount.u128())
-        .map(Uint128)
-        .ok_or_else(|| {
-            StdError::generic_err("Amount of tokens is bigger than allowed to transfer")
-        })?;
+    period: u64,
+) -> StdResult<HandleResponse> {
+    if period == 0 {
+        return Err(StdError::generic_err("period must be greater than zero"));
+    }
 
-    allowances.set(allowed_addr, allowance)?;
+    let spender = deps.api.canonical_address(&spender)?;
 
-    Ok(())
+    let mut allowances = Allowances::for_owner(&deps.api, &env.message.sender, &mut deps.storage)?;
+    ensure_not_self_allowance(allowances.owner(), &spender)?;
+    allowances.set(
+        &spender,
+        Allowance {
+            is_allowed: true,
+            amount,
+            expires: Expiration::Never,
+            period: Some(period),
+            period_reset_at: Some(env.block.time.saturating_add(period)),
+            per_period_limit: Some(amount),
+            unlimited: false,
+        },
+    )?;
+    Ok(HandleResponse::default())
 }
 
-pub fn query<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
-    msg: QueryMsg,
-) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetBalance { user } => to_binary(&query_balance(deps, user)?),
+fn try_increase_allowance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    spender: HumanAddr,
+    Uint128(amount): Uint128,
+    expires: Option<Expiration>,
+) -> StdResult<HandleResponse> {
+    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
+    let spender_addr = deps.api.canonical_address(&spender)?;
+    ensure_not_self_allowance(&sender_addr, &spender_addr)?;
+    let expires = match expires {
+        Some(expires) => Some(expires),
+        None => default_expiration(&deps.storage, &env.block)?,
+    };
+
+    let mut allowances = Allowances::new(&sender_addr, &mut deps.storage);
+    let mut allowance = allowances.get(&spender_addr)?.unwrap_or(Allowance {
+        is_allowed: true,
+        amount: Uint128(0),
+        expires: Expiration::Never,
+        ..Allowance::default()
+    });
+    if amount == u128::MAX {
+        allowance.unlimited = true;
     }
+    if allowance.unlimited {
+        allowance.amount = Uint128(u128::MAX);
+    } else {
+        allowance.amount = add_uint128(allowance.amount, Uint128(amount))?;
+    }
+    if let Some(expires) = expires {
+        allowance.expires = expires;
+    }
+    let is_allowed = allowance.is_allowed;
+    allowances.set(&spender_addr, allowance)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log(ACTION, "increase_allowance"),
+            log(OWNER, env.message.sender),
+            log(SPENDER, spender),
+            log(AMOUNT, amount),
+            log("is_allowed", is_allowed),
+        ],
+        data: None,
+    })
 }
 
-fn query_balance<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
-    user: HumanAddr,
-) -> StdResult<BalanceResponse> {
-    let user = deps.api.canonical_address(&user)?;
+fn try_decrease_allowance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    spender: HumanAddr,
+    Uint128(amount): Uint128,
+) -> StdResult<HandleResponse> {
+    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
+    let spender_addr = deps.api.canonical_address(&spender)?;
+    ensure_not_self_allowance(&sender_addr, &spender_addr)?;
 
-    let balances = ReadOnlyBalances::new(&deps.storage);
-    let balance = balances.get(&user)?;
-    Ok(BalanceResponse {
-        amount: Uint128(balance),
+    let mut allowances = Allowances::new(&sender_addr, &mut deps.storage);
+    let mut allowance = allowances.get(&spender_addr)?.unwrap_or(Allowance {
+        is_allowed: false,
+        amount: Uint128(0),
+        expires: Expiration::Never,
+        ..Allowance::default()
+    });
+    allowance.amount = Uint128(allowance.amount.u128().saturating_sub(amount));
+    allowance.unlimited = allowance.unlimited && amount == 0;
+    let is_allowed = allowance.is_allowed && allowance.amount.u128() > 0;
+    if allowance.amount.u128() == 0 {
+        allowances.remove(&spender_addr)?;
+    } else {
+        allowances.set(&spender_addr, allowance)?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log(ACTION, "decrease_allowance"),
+            log(OWNER, env.message.sender),
+            log(SPENDER, spender),
+            log(AMOUNT, amount),
+            log("is_allowed", is_allowed),
+        ],
+        data: None,
     })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::state::ReadOnlyAllowances;
-    use cosmwasm_std::from_binary;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env};
-
-    const INITIAL_TOTAL_SUPPLY: u128 = 100_000_000;
-    const INITIAL_BALANCE: u128 = 1_000_000;
-    const ALLOWANCE_AMOUNT: u128 = 10_000;
-    const TOTAL_SUPPLY: u128 = INITIAL_TOTAL_SUPPLY + INITIAL_BALANCE;
+fn try_revoke_allowance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    spender: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
+    let spender_addr = deps.api.canonical_address(&spender)?;
+
+    let mut allowances = Allowances::new(&sender_addr, &mut deps.storage);
+    allowances.remove(&spender_addr)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log(ACTION, "revoke_allowance"),
+            log(OWNER, env.message.sender),
+            log(SPENDER, spender),
+            log(AMOUNT, 0),
+            log("is_allowed", false),
+        ],
+        data: None,
+    })
+}
+
+fn try_prune_allowance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner: HumanAddr,
+    spender: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let spender_addr = deps.api.canonical_address(&spender)?;
+
+    let mut allowances = Allowances::for_owner(&deps.api, &owner, &mut deps.storage)?;
+    let allowance = allowances
+        .get(&spender_addr)?
+        .ok_or_else(|| StdError::generic_err("allowance not found"))?;
+    if !allowance.expires.is_expired(&env.block) {
+        return Err(StdError::generic_err("allowance has not expired"));
+    }
+    allowances.remove(&spender_addr)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log(ACTION, "prune_allowance"),
+            log(OWNER, owner),
+            log(SPENDER, spender),
+        ],
+        data: None,
+    })
+}
+
+fn try_transfer_from<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+    to: HumanAddr,
+    amount: Uint128,
+) -> StdResult<HandleResponse> {
+    ensure_nonzero(amount)?;
+    ensure_not_token_contract(&env, &to)?;
+
+    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
+    let from_addr = deps.api.canonical_address(&from)?;
+    let to_addr = deps.api.canonical_address(&to)?;
+
+    let state = State::read(&deps.storage).load()?;
+    ensure_min_transfer(&state, amount)?;
+
+    // the burn fee is drawn from the owner, so it counts against the allowance as well
+    let charged = add_amount(amount.u128(), state.burn_fee.u128())?;
+    let from_balance = ReadOnlyBalances::new(&deps.storage).get(&from_addr)?;
+    debit(from_balance, charged)?;
+
+    let remaining_allowance = process_allowance(deps, &env, &from, &sender_addr, Uint128(charged))?;
+
+    let mut messages = vec![];
+    let outcome =
+        try_transfer_charged(deps, &env.block, from_addr, to_addr, amount, &mut messages)?;
+
+    let mut logs = vec![
+        log(ACTION, "transfer_from"),
+        log(FROM, from),
+        log(TO, to),
+        log(BY, env.message.sender),
+        log(AMOUNT, amount),
+        log("remaining_allowance", remaining_allowance),
+    ];
+    if outcome.fee > 0 {
+        logs.push(log("fee", outcome.fee));
+    }
+    if outcome.burn_fee > 0 {
+        logs.push(log("burn_fee", outcome.burn_fee));
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+fn try_transfer_from_partial<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+    to: HumanAddr,
+    amount: Uint128,
+) -> StdResult<HandleResponse> {
+    ensure_nonzero(amount)?;
+    ensure_not_token_contract(&env, &to)?;
+
+    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
+    let from_addr = deps.api.canonical_address(&from)?;
+    let to_addr = deps.api.canonical_address(&to)?;
+
+    let mut allowance = ReadOnlyAllowances::new(&from_addr, &deps.storage)
+        .get(&sender_addr)?
+        .filter(|allowance| allowance.is_allowed)
+        .ok_or(ContractError::Unauthorized)?;
+    // process_allowance refills a recurring allowance whose period has rolled over
+    allowance.refresh(&env.block);
+    let spendable = spendable_by_allowance(
+        &deps.storage,
+        &env.block,
+        &from_addr,
+        allowance.amount.u128(),
+    )?;
+
+    let moved = Uint128(amount.u128().min(spendable));
+    ensure_nonzero(moved)?;
+
+    let state = State::read(&deps.storage).load()?;
+    ensure_min_transfer(&state, moved)?;
+
+    let charged = add_amount(moved.u128(), state.burn_fee.u128())?;
+    let remaining_allowance = process_allowance(deps, &env, &from, &sender_addr, Uint128(charged))?;
+
+    let mut messages = vec![];
+    let outcome = try_transfer_charged(deps, &env.block, from_addr, to_addr, moved, &mut messages)?;
+
+    let mut logs = vec![
+        log(ACTION, "transfer_from_partial"),
+        log(FROM, from),
+        log(TO, to),
+        log(BY, env.message.sender),
+        log(AMOUNT, moved),
+        log("remaining_allowance", remaining_allowance),
+    ];
+    if outcome.fee > 0 {
+        logs.push(log("fee", outcome.fee));
+    }
+    if outcome.burn_fee > 0 {
+        logs.push(log("burn_fee", outcome.burn_fee));
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+fn try_burn_from<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+    amount: Uint128,
+) -> StdResult<HandleResponse> {
+    ensure_nonzero(amount)?;
+    ensure_can_burn(deps, &env.message.sender)?;
+
+    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
+    let from_addr = deps.api.canonical_address(&from)?;
+
+    let remaining_allowance = process_allowance(deps, &env, &from, &sender_addr, amount)?;
+
+    try_burn_inner(deps, from_addr, amount)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log(ACTION, "burn_from"),
+            log(FROM, from),
+            log(BY, env.message.sender),
+            log(AMOUNT, amount),
+            log("remaining_allowance", remaining_allowance),
+        ],
+        data: None,
+    })
+}
+
+fn try_mint<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    recipient: HumanAddr,
+    Uint128(amount): Uint128,
+) -> StdResult<HandleResponse> {
+    ensure_nonzero(Uint128(amount))?;
+
+    ensure_minter(deps, &env.message.sender, "mint")?;
+
+    let mut state = State::read(&deps.storage).load()?;
+    if let Some(limit) = state.max_mint_per_tx {
+        if amount > limit.u128() {
+            return Err(StdError::generic_err("mint exceeds per-tx limit"));
+        }
+    }
+
+    // validate the new supply before touching any balance, so a failed mint writes nothing
+    state.total_supply = add_uint128(state.total_supply, Uint128(amount))?;
+    if let Some(cap) = state.cap {
+        if state.total_supply > cap {
+            return Err(StdError::generic_err("minting would exceed cap"));
+        }
+    }
+
+    let recipient_addr = deps.api.canonical_address(&recipient)?;
+
+    let mut balances = Balances::new(&mut deps.storage);
+    let recipient_balance = balances.get(&recipient_addr)?;
+    let new_recipient_balance = add_amount(recipient_balance, amount)?;
+    balances.set(&recipient_addr, new_recipient_balance)?;
+
+    State::write(&mut deps.storage).save(&state)?;
+    debug_assert!(state.total_supply.u128() >= new_recipient_balance);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log(ACTION, "mint"),
+            log(TO, recipient),
+            log(AMOUNT, amount),
+            log("new_total_supply", state.total_supply),
+        ],
+        data: None,
+    })
+}
+
+fn try_mint_once<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    recipient: HumanAddr,
+    amount: Uint128,
+    nonce: String,
+) -> StdResult<HandleResponse> {
+    ensure_minter(deps, &env.message.sender, "mint")?;
+
+    if ReadOnlyMintNonces::new(&deps.storage).contains(&nonce) {
+        return Err(StdError::generic_err("duplicate mint"));
+    }
+
+    let mut resp = try_mint(deps, env, recipient, amount)?;
+    MintNonces::new(&mut deps.storage).add(&nonce)?;
+
+    resp.log.push(log("nonce", nonce));
+    Ok(resp)
+}
+
+fn try_batch_mint<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    mints: Vec<MintAction>,
+) -> StdResult<HandleResponse> {
+    if mints.len() > MAX_BATCH_TRANSFERS {
+        return Err(StdError::generic_err(format!(
+            "Batch must not contain more than {} mints",
+            MAX_BATCH_TRANSFERS
+        )));
+    }
+
+    ensure_minter(deps, &env.message.sender, "mint")?;
+
+    let mut actions = Vec::with_capacity(mints.len());
+    let mut total = 0u128;
+    for MintAction { recipient, amount } in mints {
+        ensure_nonzero(amount)?;
+        total = add_amount(total, amount.u128())?;
+        actions.push((deps.api.canonical_address(&recipient)?, amount.u128()));
+    }
+
+    // check the limits on the whole batch up front so no recipient is credited on failure
+    let state = State::read(&deps.storage).load()?;
+    if let Some(limit) = state.max_mint_per_tx {
+        if total > limit.u128() {
+            return Err(StdError::generic_err("mint exceeds per-tx limit"));
+        }
+    }
+    let new_total_supply = add_amount(state.total_supply.u128(), total)?;
+    if let Some(cap) = state.cap {
+        if new_total_supply > cap.u128() {
+            return Err(StdError::generic_err("minting would exceed cap"));
+        }
+    }
+
+    let mut balances = Balances::new(&mut deps.storage);
+    for (recipient_addr, amount) in actions {
+        let recipient_balance = balances.get(&recipient_addr)?;
+        balances.set(&recipient_addr, add_amount(recipient_balance, amount)?)?;
+    }
+
+    State::write(&mut deps.storage).update(|mut state| {
+        state.total_supply = Uint128(new_total_supply);
+        Ok(state)
+    })?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log(ACTION, "batch_mint"),
+            log(AMOUNT, total),
+            log("new_total_supply", new_total_supply),
+        ],
+        data: None,
+    })
+}
+
+fn try_distribute_pro_rata<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    Uint128(total): Uint128,
+) -> StdResult<HandleResponse> {
+    ensure_nonzero(Uint128(total))?;
+    ensure_minter(deps, &env.message.sender, "mint")?;
+
+    let state = State::read(&deps.storage).load()?;
+    if let Some(limit) = state.max_mint_per_tx {
+        if total > limit.u128() {
+            return Err(StdError::generic_err("mint exceeds per-tx limit"));
+        }
+    }
+    let new_total_supply = add_amount(state.total_supply.u128(), total)?;
+    if let Some(cap) = state.cap {
+        if new_total_supply > cap.u128() {
+            return Err(StdError::generic_err("minting would exceed cap"));
+        }
+    }
+
+    // collect every holder first so shares are taken from pre-distribution balances;
+    // the burn address holds parked tokens and gets nothing
+    let mut holders = vec![];
+    let mut held = 0u128;
+    let balances = ReadOnlyBalances::new(&deps.storage);
+    let mut start_after = None;
+    loop {
+        let page = balances.range(start_after.as_ref(), MAX_LIMIT as usize)?;
+        match page.last() {
+            Some((addr, _)) => start_after = Some(addr.clone()),
+            None => break,
+        }
+        for (addr, balance) in page {
+            if addr != state.burn_address {
+                held = add_amount(held, balance)?;
+                holders.push((addr, balance));
+            }
+        }
+    }
+    if holders.is_empty() {
+        return Err(StdError::generic_err("No holders to distribute to"));
+    }
+
+    let mut shares = holders
+        .iter()
+        .map(|(_, balance)| mul_div(total, *balance, held))
+        .collect::<StdResult<Vec<_>>>()?;
+    // each share is rounded down, so hand the leftover to the largest holder
+    let distributed: u128 = shares.iter().sum();
+    let largest = holders
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, (_, balance))| *balance)
+        .map(|(i, _)| i)
+        .unwrap_or_default();
+    shares[largest] += total - distributed;
+
+    let mut balances = Balances::new(&mut deps.storage);
+    for ((addr, balance), share) in holders.iter().zip(shares) {
+        if share > 0 {
+            balances.set(addr, add_amount(*balance, share)?)?;
+        }
+    }
+
+    State::write(&mut deps.storage).update(|mut state| {
+        state.total_supply = Uint128(new_total_supply);
+        Ok(state)
+    })?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log(ACTION, "distribute_pro_rata"),
+            log(AMOUNT, total),
+            log("holders", holders.len()),
+            log("new_total_supply", new_total_supply),
+        ],
+        data: None,
+    })
+}
+
+fn try_set_paused<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    paused: bool,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "pause")?;
+
+    State::write(&mut deps.storage).update(|mut state| {
+        state.paused = paused;
+        Ok(state)
+    })?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_update_minter<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    new_minter: Option<HumanAddr>,
+) -> StdResult<HandleResponse> {
+    ensure_minter(deps, &env.message.sender, "update minters")?;
+
+    let sender_addr = deps.api.canonical_address(&env.message.sender)?;
+    let new_minter_addr = new_minter
+        .map(|new_minter| deps.api.canonical_address(&new_minter))
+        .transpose()?;
+
+    let mut minters = Minters::new(&mut deps.storage);
+    minters.remove(&sender_addr);
+    if let Some(new_minter_addr) = new_minter_addr {
+        minters.add(&new_minter_addr)?;
+    }
+
+    Ok(HandleResponse::default())
+}
+
+fn try_add_minter<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    minter: HumanAddr,
+) -> StdResult<HandleResponse> {
+    ensure_minter(deps, &env.message.sender, "add minters")?;
+
+    let minter_addr = deps.api.canonical_address(&minter)?;
+    Minters::new(&mut deps.storage).add(&minter_addr)?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_remove_minter<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    minter: HumanAddr,
+) -> StdResult<HandleResponse> {
+    ensure_minter(deps, &env.message.sender, "remove minters")?;
+
+    let minter_addr = deps.api.canonical_address(&minter)?;
+    Minters::new(&mut deps.storage).remove(&minter_addr);
+
+    Ok(HandleResponse::default())
+}
+
+fn try_update_marketing<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    project: Option<String>,
+    description: Option<String>,
+    marketing: Option<HumanAddr>,
+) -> StdResult<HandleResponse> {
+    ensure_marketing(deps, &env.message.sender)?;
+
+    if let Some(marketing) = &marketing {
+        deps.api.canonical_address(marketing)?;
+    }
+
+    MarketingInfo::write(&mut deps.storage).update(|mut info| {
+        if project.is_some() {
+            info.project = project;
+        }
+        if description.is_some() {
+            info.description = description;
+        }
+        if marketing.is_some() {
+            info.marketing = marketing;
+        }
+        Ok(info)
+    })?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_upload_logo<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    logo: Logo,
+) -> StdResult<HandleResponse> {
+    ensure_marketing(deps, &env.message.sender)?;
+
+    if let Logo::Embedded(data) = &logo {
+        if data.as_slice().len() > MAX_LOGO_SIZE {
+            return Err(StdError::generic_err("Logo is too big"));
+        }
+        if logo_mime_type(data.as_slice()).is_none() {
+            return Err(StdError::generic_err("Logo must be a PNG or SVG image"));
+        }
+    }
+
+    MarketingInfo::write(&mut deps.storage).update(|mut info| {
+        info.logo = Some(logo);
+        Ok(info)
+    })?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_update_admin<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    new_admin: HumanAddr,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "update the admin")?;
+    deps.api.canonical_address(&new_admin)?;
+
+    State::write(&mut deps.storage).update(|mut state| {
+        state.admin = new_admin;
+        Ok(state)
+    })?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_update_fee<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    fee_bps: u16,
+    fee_collector: HumanAddr,
+    fee_rounding: Option<Rounding>,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "update the fee")?;
+    ensure_valid_fee(fee_bps)?;
+    deps.api.canonical_address(&fee_collector)?;
+
+    State::write(&mut deps.storage).update(|mut state| {
+        state.fee_bps = fee_bps;
+        state.fee_collector = fee_collector;
+        if let Some(fee_rounding) = fee_rounding {
+            state.fee_rounding = fee_rounding;
+        }
+        Ok(state)
+    })?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_update_mint_limit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    max_mint_per_tx: Option<Uint128>,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "update the mint limit")?;
+
+    State::write(&mut deps.storage).update(|mut state| {
+        state.max_mint_per_tx = max_mint_per_tx;
+        Ok(state)
+    })?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_update_daily_limit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    daily_transfer_limit: Option<Uint128>,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "update the daily limit")?;
+
+    State::write(&mut deps.storage).update(|mut state| {
+        state.daily_transfer_limit = daily_transfer_limit;
+        Ok(state)
+    })?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_update_cooldown<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    cooldown_blocks: Option<u64>,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "update the cooldown")?;
+
+    State::write(&mut deps.storage).update(|mut state| {
+        state.cooldown_blocks = cooldown_blocks;
+        Ok(state)
+    })?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_freeze<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    address: HumanAddr,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "freeze accounts")?;
+
+    let addr = deps.api.canonical_address(&address)?;
+    FrozenAccounts::new(&mut deps.storage).add(&addr)?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_unfreeze<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    address: HumanAddr,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "unfreeze accounts")?;
+
+    let addr = deps.api.canonical_address(&address)?;
+    FrozenAccounts::new(&mut deps.storage).remove(&addr);
+
+    Ok(HandleResponse::default())
+}
+
+fn try_snapshot<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "take snapshots")?;
+
+    let height = env.block.height;
+    let mut checkpoints = Checkpoints::read(&deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    if checkpoints
+        .heights
+        .last()
+        .map_or(true, |last| *last < height)
+    {
+        checkpoints.heights.push(height);
+        Checkpoints::write(&mut deps.storage).save(&checkpoints)?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log(ACTION, "snapshot"), log("height", height)],
+        data: None,
+    })
+}
+
+fn try_force_transfer<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+    to: HumanAddr,
+    amount: Uint128,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "force transfers")?;
+    ensure_nonzero(amount)?;
+
+    let from_addr = deps.api.canonical_address(&from)?;
+    let to_addr = deps.api.canonical_address(&to)?;
+
+    // clawback is bounded to accounts that have already been frozen
+    let frozen = ReadOnlyFrozenAccounts::new(&deps.storage);
+    if !frozen.contains(&from_addr) {
+        return Err(StdError::generic_err(
+            "Force transfer requires a frozen account",
+        ));
+    }
+    if frozen.contains(&to_addr) {
+        return Err(StdError::generic_err("Recipient account is frozen"));
+    }
+
+    move_balance(deps, from_addr.clone(), to_addr.clone(), amount)?;
+    // hooks hear about clawbacks too, even though the other transfer checks are bypassed
+    let mut messages = vec![];
+    notify_hooks(
+        deps,
+        &from_addr,
+        &to_addr,
+        amount,
+        Uint128(0),
+        Uint128(0),
+        &mut messages,
+    )?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log(ACTION, "force_transfer"),
+            log(FROM, from),
+            log(TO, to),
+            log(AMOUNT, amount),
+        ],
+        data: None,
+    })
+}
+
+fn try_sweep<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    denom: String,
+    recipient: HumanAddr,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "sweep")?;
+
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address.clone(), &denom)?;
+    if balance.amount.u128() == 0 {
+        return Err(StdError::generic_err(format!("No {} to sweep", denom)));
+    }
+
+    Ok(HandleResponse {
+        messages: vec![BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: recipient.clone(),
+            amount: vec![balance.clone()],
+        }
+        .into()],
+        log: vec![
+            log(ACTION, "sweep"),
+            log(TO, recipient),
+            log(AMOUNT, format!("{}{}", balance.amount, balance.denom)),
+        ],
+        data: None,
+    })
+}
+
+fn try_add_hook<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    contract: HumanAddr,
+    code_hash: String,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "add hooks")?;
+    deps.api.canonical_address(&contract)?;
+
+    let mut hooks = TransferHooks::read(&deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    if hooks.hooks.iter().any(|hook| hook.contract == contract) {
+        return Err(StdError::generic_err("Hook is already registered"));
+    }
+    // every hook adds a message to each transfer, so the list is kept short
+    if hooks.hooks.len() >= MAX_TRANSFER_HOOKS {
+        return Err(StdError::generic_err(format!(
+            "Cannot register more than {} hooks",
+            MAX_TRANSFER_HOOKS
+        )));
+    }
+    hooks.hooks.push(Hook {
+        contract,
+        code_hash,
+    });
+    TransferHooks::write(&mut deps.storage).save(&hooks)?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_remove_hook<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    contract: HumanAddr,
+) -> StdResult<HandleResponse> {
+    ensure_admin(deps, &env.message.sender, "remove hooks")?;
+
+    let mut hooks = TransferHooks::read(&deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let len = hooks.hooks.len();
+    hooks.hooks.retain(|hook| hook.contract != contract);
+    if hooks.hooks.len() == len {
+        return Err(StdError::generic_err("Hook is not registered"));
+    }
+    TransferHooks::write(&mut deps.storage).save(&hooks)?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_set_vesting<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    address: HumanAddr,
+    locked: Uint128,
+    start: u64,
+    cliff: u64,
+    duration: u64,
+) -> StdResult<HandleResponse> {
+    ensure_minter(deps, &env.message.sender, "set vesting")?;
+    if cliff > duration {
+        return Err(StdError::generic_err(
+            "Vesting cliff must not exceed duration",
+        ));
+    }
+
+    let addr = deps.api.canonical_address(&address)?;
+    let mut vestings = Vestings::new(&mut deps.storage);
+    if locked.u128() == 0 {
+        vestings.remove(&addr)?;
+    } else {
+        vestings.set(
+            &addr,
+            &Vesting {
+                locked,
+                start,
+                cliff,
+                duration,
+            },
+        )?;
+    }
+
+    Ok(HandleResponse::default())
+}
+
+struct TransferOutcome {
+    from_balance: u128,
+    to_balance: u128,
+    fee: u128,
+    burn_fee: u128,
+}
+
+// every user-initiated outgoing transfer goes through here, so fees can't be skipped
+// by picking a different entry point; the fee comes out of `amount`, while the burn
+// fee is taken from the sender on top of it
+fn try_transfer_charged<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block: &BlockInfo,
+    from: CanonicalAddr,
+    to: CanonicalAddr,
+    amount: Uint128,
+    messages: &mut Vec<CosmosMsg>,
+) -> StdResult<TransferOutcome> {
+    let state = State::read(&deps.storage).load()?;
+    ensure_min_transfer(&state, amount)?;
+
+    // locked tokens can't pay the burn fee, so check it against the unlocked balance
+    // before anything moves
+    let burn_fee = state.burn_fee.u128();
+    if burn_fee > 0 {
+        let unlocked = unlocked_balance(&deps.storage, block, &from)?;
+        debit(unlocked, add_amount(amount.u128(), burn_fee)?)?;
+    }
+
+    let fee = compute_fee(amount.u128(), state.fee_bps, state.fee_rounding);
+    if fee > 0 {
+        let collector_addr = deps.api.canonical_address(&state.fee_collector)?;
+        try_transfer_inner(deps, block, from.clone(), collector_addr, Uint128(fee))?;
+    }
+
+    let (mut from_balance, to_balance) = try_transfer_inner(
+        deps,
+        block,
+        from.clone(),
+        to.clone(),
+        Uint128(amount.u128() - fee),
+    )?;
+    // only the net recipient starts a cooldown; the fee collector and a
+    // sender paying itself are not locked by the transfer
+    if state.cooldown_blocks.is_some() && from != to {
+        LastReceived::new(&mut deps.storage).set(&to, block.height)?;
+    }
+
+    if burn_fee > 0 {
+        try_burn_inner(deps, from.clone(), Uint128(burn_fee))?;
+        from_balance = sub_amount(from_balance, burn_fee)?;
+    }
+
+    // one notification for the whole transfer, fee legs included
+    notify_hooks(
+        deps,
+        &from,
+        &to,
+        amount,
+        Uint128(fee),
+        Uint128(burn_fee),
+        messages,
+    )?;
+
+    Ok(TransferOutcome {
+        from_balance,
+        to_balance,
+        fee,
+        burn_fee,
+    })
+}
+
+fn try_transfer_inner<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block: &BlockInfo,
+    from: CanonicalAddr,
+    to: CanonicalAddr,
+    amount: Uint128,
+) -> StdResult<(u128, u128)> {
+    let frozen = ReadOnlyFrozenAccounts::new(&deps.storage);
+    if frozen.contains(&from) {
+        return Err(StdError::generic_err("Sender account is frozen"));
+    }
+    if frozen.contains(&to) {
+        return Err(StdError::generic_err("Recipient account is frozen"));
+    }
+
+    if ReadOnlyVestings::new(&deps.storage).get(&from)?.is_some() {
+        let unlocked = unlocked_balance(&deps.storage, block, &from)?;
+        if amount.u128() > unlocked {
+            return Err(StdError::generic_err(format!(
+                "Transfer exceeds unlocked balance of {}",
+                unlocked
+            )));
+        }
+    }
+
+    record_outflow(&mut deps.storage, block, &from, amount)?;
+    ensure_cooled_down(&deps.storage, block, &from)?;
+
+    move_balance(deps, from, to, amount)
+}
+
+fn notify_hooks<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    from: &CanonicalAddr,
+    to: &CanonicalAddr,
+    amount: Uint128,
+    fee: Uint128,
+    burn_fee: Uint128,
+    messages: &mut Vec<CosmosMsg>,
+) -> StdResult<()> {
+    let hooks = TransferHooks::read(&deps.storage)
+        .may_load()?
+        .unwrap_or_default()
+        .hooks;
+    if !hooks.is_empty() {
+        let hook_msg = to_binary(&TransferHookMsg::TransferHook {
+            from: deps.api.human_address(from)?,
+            to: deps.api.human_address(to)?,
+            amount,
+            fee,
+            burn_fee,
+        })?;
+        messages.extend(hooks.into_iter().map(|hook| {
+            CosmosMsg::from(WasmMsg::Execute {
+                contract_addr: hook.contract,
+                callback_code_hash: hook.code_hash,
+                msg: hook_msg.clone(),
+                send: vec![],
+            })
+        }));
+    }
+    Ok(())
+}
+
+fn record_outflow<S: Storage>(
+    storage: &mut S,
+    block: &BlockInfo,
+    from: &CanonicalAddr,
+    Uint128(amount): Uint128,
+) -> StdResult<()> {
+    let limit = match State::read(storage).load()?.daily_transfer_limit {
+        Some(limit) => limit.u128(),
+        None => return Ok(()),
+    };
+
+    let mut outflow = current_outflow(storage, block, from)?;
+
+    let spent = add_amount(outflow.spent.u128(), amount)?;
+    if spent > limit {
+        return Err(StdError::generic_err(format!(
+            "Transfer exceeds daily limit, {} remaining",
+            limit.saturating_sub(outflow.spent.u128())
+        )));
+    }
+    outflow.spent = Uint128(spent);
+    Outflows::new(storage).set(from, &outflow)
+}
+
+fn current_outflow<S: Storage>(
+    storage: &S,
+    block: &BlockInfo,
+    from: &CanonicalAddr,
+) -> StdResult<Outflow> {
+    Ok(ReadOnlyOutflows::new(storage)
+        .get(from)?
+        .filter(|outflow| block.time < outflow.window_start.saturating_add(DAY_SECONDS))
+        .unwrap_or(Outflow {
+            window_start: block.time,
+            spent: Uint128(0),
+        }))
+}
+
+fn unlocked_balance<S: Storage>(
+    storage: &S,
+    block: &BlockInfo,
+    addr: &CanonicalAddr,
+) -> StdResult<u128> {
+    let balance = ReadOnlyBalances::new(storage).get(addr)?;
+    Ok(match ReadOnlyVestings::new(storage).get(addr)? {
+        Some(vesting) => balance.saturating_sub(vesting.locked_at(block.time)),
+        None => balance,
+    })
+}
+
+// what a spender holding `allowance` could move out of `owner` right now; the burn fee
+// comes out of the same allowance and unlocked balance, but not the daily limit
+fn spendable_by_allowance<S: Storage>(
+    storage: &S,
+    block: &BlockInfo,
+    owner: &CanonicalAddr,
+    allowance: u128,
+) -> StdResult<u128> {
+    let burn_fee = State::read(storage).load()?.burn_fee.u128();
+    let unlocked = unlocked_balance(storage, block, owner)?;
+    Ok(spendable_balance(storage, block, owner)?
+        .min(unlocked.saturating_sub(burn_fee))
+        .min(allowance.saturating_sub(burn_fee)))
+}
+
+// what a transfer out of `addr` could move right now: the unlocked balance, capped by
+// whatever is left of the daily transfer limit
+fn spendable_balance<S: Storage>(
+    storage: &S,
+    block: &BlockInfo,
+    addr: &CanonicalAddr,
+) -> StdResult<u128> {
+    let unlocked = unlocked_balance(storage, block, addr)?;
+    Ok(match State::read(storage).load()?.daily_transfer_limit {
+        Some(limit) => {
+            let spent = current_outflow(storage, block, addr)?.spent.u128();
+            unlocked.min(limit.u128().saturating_sub(spent))
+        }
+        None => unlocked,
+    })
+}
+
+// receipts are only recorded while a cooldown is configured
+fn ensure_cooled_down<S: Storage>(
+    storage: &S,
+    block: &BlockInfo,
+    from: &CanonicalAddr,
+) -> StdResult<()> {
+    let cooldown = match State::read(storage).load()?.cooldown_blocks {
+        Some(cooldown) => cooldown,
+        None => return Ok(()),
+    };
+
+    if let Some(height) = ReadOnlyLastReceived::new(storage).get(from)? {
+        let unlocked_at = height.saturating_add(cooldown);
+        if block.height < unlocked_at {
+            return Err(StdError::generic_err(format!(
+                "Sender is in a transfer cooldown until height {}",
+                unlocked_at
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn move_balance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    from: CanonicalAddr,
+    to: CanonicalAddr,
+    Uint128(amount): Uint128,
+) -> StdResult<(u128, u128)> {
+    let mut balances = Balances::new(&mut deps.storage);
+
+    let sender_balance = balances.get(&from)?;
+    let sender_new_balance = debit(sender_balance, amount)?;
+
+    // a self-transfer would otherwise credit the balance read before the debit
+    if from == to {
+        return Ok((sender_balance, sender_balance));
+    }
+
+    let to_balance = balances.get(&to)?;
+    let recipient_new_balance = add_amount(to_balance, amount)?;
+
+    balances.set(&from, sender_new_balance)?;
+    balances.set(&to, recipient_new_balance)?;
+
+    Ok((sender_new_balance, recipient_new_balance))
+}
+
+fn try_burn_inner<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    from: CanonicalAddr,
+    Uint128(amount): Uint128,
+) -> StdResult<()> {
+    if ReadOnlyFrozenAccounts::new(&deps.storage).contains(&from) {
+        return Err(StdError::generic_err("Sender account is frozen"));
+    }
+
+    let sender_balance = ReadOnlyBalances::new(&deps.storage).get(&from)?;
+    let sender_new_balance = debit(sender_balance, amount)?;
+
+    // validate the new supply before touching the balance, so a failed burn writes nothing
+    let mut state = State::read(&deps.storage).load()?;
+    // no balance may outlive the supply backing it
+    state.total_supply = sub_uint128(state.total_supply, Uint128(amount))
+        .map_err(|_| StdError::generic_err("burn amount exceeds total supply"))?;
+
+    Balances::new(&mut deps.storage).set(&from, sender_new_balance)?;
+
+    State::write(&mut deps.storage).save(&state)?;
+
+    Ok(())
+}
+
+fn ensure_nonzero(amount: Uint128) -> StdResult<()> {
+    if amount.u128() == 0 {
+        return Err(StdError::generic_err("amount must be greater than zero"));
+    }
+    Ok(())
+}
+
+fn ensure_not_token_contract(env: &Env, to: &HumanAddr) -> StdResult<()> {
+    if *to == env.contract.address {
+        return Err(StdError::generic_err(
+            "cannot transfer to the token contract",
+        ));
+    }
+    Ok(())
+}
+
+fn ensure_min_transfer(state: &State, amount: Uint128) -> StdResult<()> {
+    if let Some(min) = state.min_transfer_amount {
+        if amount < min {
+            return Err(StdError::generic_err(format!(
+                "Transfer amount must be at least {}",
+                min
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn ensure_valid_fee(fee_bps: u16) -> StdResult<()> {
+    if fee_bps > MAX_FEE_BPS {
+        return Err(StdError::generic_err(format!(
+            "Fee must not exceed {} bps",
+            MAX_FEE_BPS
+        )));
+    }
+    Ok(())
+}
+
+fn compute_fee(amount: u128, fee_bps: u16, rounding: Rounding) -> u128 {
+    // split the multiplication so large amounts can't overflow
+    let fee_bps = u128::from(fee_bps);
+    let fractional = amount % 10_000 * fee_bps;
+    let fee = amount / 10_000 * fee_bps + fractional / 10_000;
+    let remainder = fractional % 10_000;
+
+    let round_up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => remainder > 0,
+        Rounding::Nearest => remainder > 5_000 || (remainder == 5_000 && fee % 2 == 1),
+    };
+    // the fee is at most a tenth of `amount`, so this can't overflow
+    fee + u128::from(round_up)
+}
+
+// the admin and minters are public through queries, so naming them leaks nothing
+fn ensure_admin<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    sender: &HumanAddr,
+    action: &str,
+) -> StdResult<()> {
+    let sender_addr = deps.api.canonical_address(sender)?;
+
+    let state = State::read(&deps.storage).load()?;
+    let admin = deps.api.canonical_address(&state.admin)?;
+
+    if admin != sender_addr {
+        return Err(StdError::generic_err(format!(
+            "only admin {} may {}",
+            state.admin, action
+        )));
+    }
+    Ok(())
+}
+
+fn ensure_minter<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    sender: &HumanAddr,
+    action: &str,
+) -> StdResult<()> {
+    let sender_addr = deps.api.canonical_address(sender)?;
+
+    let minters = ReadOnlyMinters::new(&deps.storage);
+    if !minters.contains(&sender_addr) {
+        let minters = minters
+            .all()
+            .iter()
+            .map(|minter| deps.api.human_address(minter).map(|minter| minter.0))
+            .collect::<StdResult<Vec<_>>>()?;
+        if minters.is_empty() {
+            return Err(StdError::generic_err(format!(
+                "no minter is set, so nobody may {}",
+                action
+            )));
+        }
+        return Err(StdError::generic_err(format!(
+            "only minter {} may {}",
+            minters.join(", "),
+            action
+        )));
+    }
+    Ok(())
+}
+
+fn ensure_can_burn<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    sender: &HumanAddr,
+) -> StdResult<()> {
+    let state = State::read(&deps.storage).load()?;
+    if state.burnable_by_holders {
+        return Ok(());
+    }
+
+    let sender_addr = deps.api.canonical_address(sender)?;
+    if !ReadOnlyMinters::new(&deps.storage).contains(&sender_addr) {
+        return Err(ContractError::Unauthorized.into());
+    }
+    Ok(())
+}
+
+fn ensure_marketing<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    sender: &HumanAddr,
+) -> StdResult<()> {
+    let sender_addr = deps.api.canonical_address(sender)?;
+
+    let info = MarketingInfo::read(&deps.storage).load()?;
+    let marketing = match info.marketing {
+        Some(marketing) => deps.api.canonical_address(&marketing)?,
+        None => return Err(ContractError::Unauthorized.into()),
+    };
+
+    if marketing != sender_addr {
+        return Err(ContractError::Unauthorized.into());
+    }
+    Ok(())
+}
+
+fn logo_mime_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(PNG_HEADER) {
+        Some("image/png")
+    } else if data.starts_with(b"<?xml") || data.starts_with(b"<svg") {
+        Some("image/svg+xml")
+    } else {
+        None
+    }
+}
+
+fn ensure_not_paused<S: Storage>(storage: &S) -> StdResult<()> {
+    let state = State::read(storage).load()?;
+    if state.paused {
+        return Err(ContractError::Paused.into());
+    }
+    Ok(())
+}
+
+fn ensure_not_self_allowance(owner: &CanonicalAddr, spender: &CanonicalAddr) -> StdResult<()> {
+    if owner.as_slice() == spender.as_slice() {
+        return Err(StdError::generic_err("cannot set allowance for yourself"));
+    }
+    Ok(())
+}
+
+fn default_expiration<S: Storage>(storage: &S, block: &BlockInfo) -> StdResult<Option<Expiration>> {
+    let state = State::read(storage).load()?;
+    Ok(state
+        .default_allowance_ttl
+        .map(|ttl| Expiration::AtTime(block.time.saturating_add(ttl))))
+}
+
+fn process_allowance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    owner: &HumanAddr,
+    allowed_addr: &CanonicalAddr,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let mut allowances = Allowances::for_owner(&deps.api, owner, &mut deps.storage)?;
+    let mut allowance = allowances
+        .get(allowed_addr)?
+        .filter(|allowance| allowance.is_allowed)
+        .ok_or(ContractError::Unauthorized)?;
+    if allowance.expires.is_expired(&env.block) {
+        return Err(StdError::generic_err("allowance expired"));
+    }
+    allowance.refresh(&env.block);
+    if allowance.unlimited {
+        return Ok(allowance.amount);
+    }
+
+    allowance.amount = sub_amount(allowance.amount.u128(), amount.u128())
+        .map(Uint128)
+        .map_err(|_| ContractError::InsufficientAllowance)?;
+
+    let remaining = allowance.amount;
+    allowances.set(allowed_addr, allowance)?;
+
+    Ok(remaining)
+}
+
+pub fn migrate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: MigrateMsg,
+) -> StdResult<MigrateResponse> {
+    let mut logs = vec![];
+    match ContractVersion::read(&deps.storage).may_load()? {
+        Some(stored) => {
+            if stored.contract != CONTRACT_NAME {
+                return Err(StdError::generic_err(format!(
+                    "Cannot migrate from a different contract: {}",
+                    stored.contract
+                )));
+            }
+
+            if parse_version(&stored.version)? >= parse_version(CONTRACT_VERSION)? {
+                return Err(StdError::generic_err(format!(
+                    "Cannot migrate from version {} to {}",
+                    stored.version, CONTRACT_VERSION
+                )));
+            }
+
+            // write back in the current shape; releases from before the burn address
+            // stored none, and the empty default would park tokens under an empty key
+            let mut state = State::read(&deps.storage).load()?;
+            if state.burn_address.is_empty() {
+                state.burn_address = burn_address(&deps.api, &env)?;
+            }
+            State::write(&mut deps.storage).save(&state)?;
+        }
+        // only the original release ran without recording a version
+        None => {
+            let token_info = msg.token_info.ok_or_else(|| {
+                StdError::generic_err("Migrating from the original release requires token_info")
+            })?;
+            migrate_baseline(deps, &env, token_info)?;
+            logs.push(log(
+                "pruned_balances",
+                prune_empty_balances(&mut deps.storage)?,
+            ));
+            logs.push(log("allowances", rebuild_spender_index(&mut deps.storage)?));
+        }
+    }
+    logs.push(log(
+        "holder_count",
+        rebuild_holder_count(&mut deps.storage)?,
+    ));
+
+    ContractVersion::write(&mut deps.storage).save(&ContractVersion {
+        contract: CONTRACT_NAME.to_string(),
+        version: CONTRACT_VERSION.to_string(),
+    })?;
+
+    Ok(MigrateResponse {
+        messages: vec![],
+        log: logs,
+        data: None,
+    })
+}
+
+// the original release kept only the minter and total supply, so everything else
+// starts out the way init would configure it
+fn migrate_baseline<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    token_info: TokenMetadata,
+) -> StdResult<()> {
+    token_info.validate()?;
+
+    let BaselineState {
+        minter,
+        total_supply,
+    } = BaselineState::read(&deps.storage).load()?;
+    let minter_addr = deps.api.canonical_address(&minter)?;
+
+    let state = State {
+        name: token_info.name,
+        symbol: token_info.symbol,
+        decimals: token_info.decimals,
+        total_supply,
+        cap: None,
+        paused: false,
+        fee_bps: 0,
+        fee_rounding: Rounding::default(),
+        fee_collector: minter.clone(),
+        admin: minter,
+        max_mint_per_tx: None,
+        min_transfer_amount: None,
+        burnable_by_holders: true,
+        default_allowance_ttl: None,
+        daily_transfer_limit: None,
+        cooldown_blocks: None,
+        burn_address: burn_address(&deps.api, env)?,
+        burn_fee: Uint128(0),
+    };
+    State::write(&mut deps.storage).save(&state)?;
+
+    Minters::new(&mut deps.storage).add(&minter_addr)?;
+    MarketingInfo::write(&mut deps.storage).save(&MarketingInfo::default())
+}
+
+// all-zero address of the same length as real ones, which no key can sign for
+fn burn_address<A: Api>(api: &A, env: &Env) -> StdResult<CanonicalAddr> {
+    let canonical_length = api.canonical_address(&env.contract.address)?.len();
+    Ok(CanonicalAddr::from(vec![0; canonical_length]))
+}
+
+fn parse_version(version: &str) -> StdResult<Vec<u64>> {
+    version
+        .split('.')
+        .map(|part| {
+            part.parse()
+                .map_err(|_| StdError::generic_err(format!("Invalid version: {}", version)))
+        })
+        .collect()
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetBalance { user } => to_binary(&query_balance(deps, user)?),
+        QueryMsg::BurnedBalance {} => to_binary(&query_burned_balance(deps)?),
+        QueryMsg::FormattedBalance { user } => to_binary(&query_formatted_balance(deps, user)?),
+        QueryMsg::Balances { users } => to_binary(&query_balances(deps, users)?),
+        QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Allowance {
+            owner,
+            spender,
+            block,
+        } => to_binary(&query_allowance(deps, owner, spender, block)?),
+        QueryMsg::Minter {} => to_binary(&query_minter(deps)?),
+        QueryMsg::AllMinters {} => to_binary(&query_all_minters(deps)?),
+        QueryMsg::IsMinter { address } => to_binary(&query_is_minter(deps, address)?),
+        QueryMsg::Admin {} => to_binary(&query_admin(deps)?),
+        QueryMsg::TopHolders { limit } => to_binary(&query_top_holders(deps, limit)?),
+        QueryMsg::AllAccounts { start_after, limit } => {
+            to_binary(&query_all_accounts(deps, start_after, limit)?)
+        }
+        QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_all_allowances(deps, owner, start_after, limit)?),
+        QueryMsg::TotalAllowanceGranted { owner, block } => {
+            to_binary(&query_total_allowance_granted(deps, owner, block)?)
+        }
+        QueryMsg::AllowancesBySpender {
+            spender,
+            start_after,
+            limit,
+        } => to_binary(&query_allowances_by_spender(
+            deps,
+            spender,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::MarketingInfo {} => to_binary(&query_marketing_info(deps)?),
+        QueryMsg::DownloadLogo {} => to_binary(&query_download_logo(deps)?),
+        QueryMsg::IsFrozen { address } => to_binary(&query_is_frozen(deps, address)?),
+        QueryMsg::VestedAmount { address, block } => {
+            to_binary(&query_vested_amount(deps, address, block)?)
+        }
+        QueryMsg::BalanceAt { user, height } => to_binary(&query_balance_at(deps, user, height)?),
+        QueryMsg::Version {} => to_binary(&query_version(deps)?),
+        QueryMsg::BalanceAndSupply { user } => to_binary(&query_balance_and_supply(deps, user)?),
+        QueryMsg::MinTransferAmount {} => to_binary(&query_min_transfer_amount(deps)?),
+        QueryMsg::HolderCount {} => to_binary(&holder_count(&deps.storage)?),
+        QueryMsg::SupplyShare { user } => to_binary(&query_supply_share(deps, user)?),
+        #[cfg(feature = "debug")]
+        QueryMsg::RawState {
+            prefix,
+            start_after,
+            limit,
+        } => to_binary(&query_raw_state(deps, prefix, start_after, limit)?),
+        QueryMsg::SpendableAllowance {
+            owner,
+            spender,
+            block,
+        } => to_binary(&query_spendable_allowance(deps, owner, spender, block)?),
+    }
+}
+
+fn query_balance<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    user: HumanAddr,
+) -> StdResult<BalanceResponse> {
+    let user = deps.api.canonical_address(&user)?;
+
+    let balances = ReadOnlyBalances::new(&deps.storage);
+    let balance = balances.get(&user)?;
+    Ok(BalanceResponse {
+        amount: Uint128(balance),
+    })
+}
+
+fn query_balance_and_supply<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    user: HumanAddr,
+) -> StdResult<BalanceAndSupplyResponse> {
+    let balance = query_balance(deps, user)?.amount;
+    let state = State::read(&deps.storage).load()?;
+    Ok(BalanceAndSupplyResponse {
+        balance,
+        total_supply: state.total_supply,
+    })
+}
+
+fn query_supply_share<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    user: HumanAddr,
+) -> StdResult<u16> {
+    let balance = query_balance(deps, user)?.amount.u128();
+    let total_supply = State::read(&deps.storage).load()?.total_supply.u128();
+    if total_supply == 0 {
+        return Ok(0);
+    }
+
+    let share = mul_div(balance, 10_000, total_supply)?.min(10_000);
+    Ok(share as u16)
+}
+
+fn query_balance_at<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    user: HumanAddr,
+    height: u64,
+) -> StdResult<BalanceResponse> {
+    let user = deps.api.canonical_address(&user)?;
+
+    let checkpoints = Checkpoints::read(&deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let checkpoint = checkpoints
+        .heights
+        .into_iter()
+        .rev()
+        .find(|checkpoint| *checkpoint <= height)
+        .ok_or_else(|| {
+            StdError::generic_err(format!("No snapshot taken at or before height {}", height))
+        })?;
+
+    let balances = ReadOnlyBalances::new(&deps.storage);
+    let balance = balances.get_at(&user, checkpoint)?;
+    Ok(BalanceResponse {
+        amount: Uint128(balance),
+    })
+}
+
+fn query_balances<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    users: Vec<HumanAddr>,
+) -> StdResult<Vec<BalanceResponse>> {
+    if users.len() > MAX_BALANCES_QUERY {
+        return Err(StdError::generic_err(format!(
+            "Cannot query more than {} balances at once",
+            MAX_BALANCES_QUERY
+        )));
+    }
+
+    users
+        .into_iter()
+        .map(|user| query_balance(deps, user))
+        .collect()
+}
+
+fn query_burned_balance<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<BalanceResponse> {
+    let burn_address = State::read(&deps.storage).load()?.burn_address;
+    let balance = ReadOnlyBalances::new(&deps.storage).get(&burn_address)?;
+    Ok(BalanceResponse {
+        amount: Uint128(balance),
+    })
+}
+
+fn query_formatted_balance<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    user: HumanAddr,
+) -> StdResult<FormattedBalanceResponse> {
+    let user = deps.api.canonical_address(&user)?;
+
+    let state = State::read(&deps.storage).load()?;
+    let balance = ReadOnlyBalances::new(&deps.storage).get(&user)?;
+    Ok(FormattedBalanceResponse {
+        raw: Uint128(balance),
+        display: format_amount(balance, state.decimals),
+    })
+}
+
+fn format_amount(amount: u128, decimals: u8) -> String {
+    let unit = 10u128.pow(decimals as u32);
+    let whole = amount / unit;
+    let fraction = amount % unit;
+    if fraction == 0 {
+        return whole.to_string();
+    }
+
+    let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+fn query_token_info<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<TokenInfoResponse> {
+    let state = State::read(&deps.storage).load()?;
+    Ok(TokenInfoResponse {
+        name: state.name,
+        symbol: state.symbol,
+        decimals: state.decimals,
+        total_supply: state.total_supply,
+        total_supply_display: format_amount(state.total_supply.u128(), state.decimals),
+    })
+}
+
+fn query_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ConfigResponse> {
+    let state = State::read(&deps.storage).load()?;
+    Ok(ConfigResponse {
+        name: state.name,
+        symbol: state.symbol,
+        decimals: state.decimals,
+        total_supply: state.total_supply,
+        cap: state.cap,
+        minters: query_all_minters(deps)?,
+        admin: state.admin,
+        paused: state.paused,
+        fee_bps: state.fee_bps,
+        fee_rounding: state.fee_rounding,
+        fee_collector: state.fee_collector,
+        max_mint_per_tx: state.max_mint_per_tx,
+        min_transfer_amount: state.min_transfer_amount,
+        burnable_by_holders: state.burnable_by_holders,
+        default_allowance_ttl: state.default_allowance_ttl,
+        daily_transfer_limit: state.daily_transfer_limit,
+        cooldown_blocks: state.cooldown_blocks,
+        burn_fee: state.burn_fee,
+    })
+}
+
+fn query_minter<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<MinterResponse> {
+    let state = State::read(&deps.storage).load()?;
+    Ok(MinterResponse { cap: state.cap })
+}
+
+fn query_all_minters<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<Vec<HumanAddr>> {
+    ReadOnlyMinters::new(&deps.storage)
+        .all()
+        .iter()
+        .map(|minter| deps.api.human_address(minter))
+        .collect()
+}
+
+fn query_is_minter<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: HumanAddr,
+) -> StdResult<bool> {
+    let addr = deps.api.canonical_address(&address)?;
+    Ok(ReadOnlyMinters::new(&deps.storage).contains(&addr))
+}
+
+fn query_admin<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>) -> StdResult<AdminResponse> {
+    let state = State::read(&deps.storage).load()?;
+    Ok(AdminResponse { admin: state.admin })
+}
+
+fn query_min_transfer_amount<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<MinTransferAmountResponse> {
+    let state = State::read(&deps.storage).load()?;
+    Ok(MinTransferAmountResponse {
+        min_transfer_amount: state.min_transfer_amount,
+    })
+}
+
+fn query_version<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<VersionResponse> {
+    // deployments that predate migrations never stored a version
+    let version = ContractVersion::read(&deps.storage)
+        .may_load()?
+        .unwrap_or_else(|| ContractVersion {
+            contract: CONTRACT_NAME.to_string(),
+            version: "0".to_string(),
+        });
+    Ok(VersionResponse {
+        contract: version.contract,
+        version: version.version,
+    })
+}
+
+fn query_all_accounts<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<AllAccountsResponse> {
+    let start_after = start_after
+        .map(|addr| deps.api.canonical_address(&addr))
+        .transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let balances = ReadOnlyBalances::new(&deps.storage);
+    let accounts = balances
+        .range(start_after.as_ref(), limit)?
+        .iter()
+        .map(|(addr, _)| deps.api.human_address(addr))
+        .collect::<StdResult<_>>()?;
+
+    Ok(AllAccountsResponse { accounts })
+}
+
+fn query_top_holders<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    limit: u32,
+) -> StdResult<TopHoldersResponse> {
+    let limit = limit.min(MAX_LIMIT) as usize;
+    let burn_address = State::read(&deps.storage).load()?.burn_address;
+
+    // a min-heap of at most `limit` entries, so the smallest kept balance is evicted first
+    let mut top = BinaryHeap::with_capacity(limit + 1);
+    let balances = ReadOnlyBalances::new(&deps.storage);
+    let mut start_after = None;
+    loop {
+        let page = balances.range(start_after.as_ref(), MAX_LIMIT as usize)?;
+        match page.last() {
+            Some((addr, _)) => start_after = Some(addr.clone()),
+            None => break,
+        }
+        for (addr, amount) in page {
+            if addr == burn_address {
+                continue;
+            }
+            top.push(Reverse((amount, addr)));
+            if top.len() > limit {
+                top.pop();
+            }
+        }
+    }
+
+    // ascending order of `Reverse` is descending order of balance
+    let holders = top
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((amount, addr))| {
+            Ok(HolderBalance {
+                address: deps.api.human_address(&addr)?,
+                amount: Uint128(amount),
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(TopHoldersResponse { holders })
+}
+
+fn query_all_allowances<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    owner: HumanAddr,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<AllAllowancesResponse> {
+    let owner = deps.api.canonical_address(&owner)?;
+    let start_after = start_after
+        .map(|addr| deps.api.canonical_address(&addr))
+        .transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let allowances = ReadOnlyAllowances::new(&owner, &deps.storage);
+    let allowances = allowances
+        .range(start_after.as_ref(), limit)?
+        .into_iter()
+        .map(|(spender, allowance)| {
+            Ok(AllowanceInfo {
+                spender: deps.api.human_address(&spender)?,
+                amount: allowance.amount,
+                is_allowed: allowance.is_allowed,
+                expires: allowance.expires,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(AllAllowancesResponse { allowances })
+}
+
+fn query_total_allowance_granted<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    owner: HumanAddr,
+    block: BlockInfo,
+) -> StdResult<Uint128> {
+    let owner = deps.api.canonical_address(&owner)?;
+
+    let allowances = ReadOnlyAllowances::new(&owner, &deps.storage).range(None, usize::MAX)?;
+    let mut total = 0u128;
+    for (_, mut allowance) in allowances {
+        if !allowance.is_allowed || allowance.expires.is_expired(&block) {
+            continue;
+        }
+        // an unlimited allowance is stored as u128::MAX, so the exposure saturates there
+        if allowance.unlimited {
+            return Ok(Uint128(u128::MAX));
+        }
+        allowance.refresh(&block);
+        total = total.saturating_add(allowance.amount.u128());
+    }
+
+    Ok(Uint128(total))
+}
+
+fn query_allowances_by_spender<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    spender: HumanAddr,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<AllowancesBySpenderResponse> {
+    let spender = deps.api.canonical_address(&spender)?;
+    let start_after = start_after
+        .map(|addr| deps.api.canonical_address(&addr))
+        .transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let owners =
+        ReadOnlySpenderIndex::new(&spender, &deps.storage).owners(start_after.as_ref(), limit);
+    let mut allowances = Vec::with_capacity(owners.len());
+    for owner in owners {
+        let allowance = ReadOnlyAllowances::new(&owner, &deps.storage)
+            .get(&spender)?
+            .ok_or_else(|| StdError::not_found("allowance"))?;
+        allowances.push(SpenderAllowanceInfo {
+            owner: deps.api.human_address(&owner)?,
+            amount: allowance.amount,
+            is_allowed: allowance.is_allowed,
+            expires: allowance.expires,
+        });
+    }
+
+    Ok(AllowancesBySpenderResponse { allowances })
+}
+
+fn query_marketing_info<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<MarketingInfoResponse> {
+    let info = MarketingInfo::read(&deps.storage).load()?;
+    Ok(MarketingInfoResponse {
+        project: info.project,
+        description: info.description,
+        marketing: info.marketing,
+        logo: info.logo.map(|logo| match logo {
+            Logo::Url(url) => LogoInfo::Url(url),
+            Logo::Embedded(_) => LogoInfo::Embedded,
+        }),
+    })
+}
+
+fn query_download_logo<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<DownloadLogoResponse> {
+    let info = MarketingInfo::read(&deps.storage).load()?;
+    match info.logo {
+        Some(Logo::Embedded(data)) => Ok(DownloadLogoResponse {
+            mime_type: logo_mime_type(data.as_slice())
+                .unwrap_or_default()
+                .to_string(),
+            data,
+        }),
+        _ => Err(StdError::not_found("embedded logo")),
+    }
+}
+
+fn query_is_frozen<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: HumanAddr,
+) -> StdResult<IsFrozenResponse> {
+    let addr = deps.api.canonical_address(&address)?;
+    let frozen = ReadOnlyFrozenAccounts::new(&deps.storage).contains(&addr);
+    Ok(IsFrozenResponse { frozen })
+}
+
+fn query_vested_amount<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: HumanAddr,
+    block: BlockInfo,
+) -> StdResult<VestedAmountResponse> {
+    let addr = deps.api.canonical_address(&address)?;
+    let resp = match ReadOnlyVestings::new(&deps.storage).get(&addr)? {
+        Some(vesting) => VestedAmountResponse {
+            locked: vesting.locked,
+            vested: Uint128(vesting.vested_at(block.time)),
+        },
+        None => VestedAmountResponse {
+            locked: Uint128(0),
+            vested: Uint128(0),
+        },
+    };
+    Ok(resp)
+}
+
+fn query_allowance<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    owner: HumanAddr,
+    spender: HumanAddr,
+    block: Option<BlockInfo>,
+) -> StdResult<AllowanceResponse> {
+    let owner = deps.api.canonical_address(&owner)?;
+    let spender = deps.api.canonical_address(&spender)?;
+
+    let allowances = ReadOnlyAllowances::new(&owner, &deps.storage);
+    let resp = match allowances.get(&spender)? {
+        Some(allowance) => AllowanceResponse {
+            amount: allowance.amount,
+            is_allowed: allowance.is_allowed,
+            is_expired: block.map_or(false, |block| allowance.expires.is_expired(&block)),
+            expires: allowance.expires,
+        },
+        None => AllowanceResponse {
+            amount: Uint128(0),
+            is_allowed: false,
+            expires: Expiration::Never,
+            is_expired: false,
+        },
+    };
+    Ok(resp)
+}
+
+fn query_spendable_allowance<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    owner: HumanAddr,
+    spender: HumanAddr,
+    block: BlockInfo,
+) -> StdResult<Uint128> {
+    let owner = deps.api.canonical_address(&owner)?;
+    let spender = deps.api.canonical_address(&spender)?;
+
+    // queries can't see the current block, so the caller supplies it for the expiry check
+    let allowance = ReadOnlyAllowances::new(&owner, &deps.storage)
+        .get(&spender)?
+        .filter(|allowance| allowance.is_allowed && !allowance.expires.is_expired(&block));
+    let allowance = match allowance {
+        Some(mut allowance) => {
+            allowance.refresh(&block);
+            allowance.amount.u128()
+        }
+        None => return Ok(Uint128(0)),
+    };
+
+    Ok(Uint128(spendable_by_allowance(
+        &deps.storage,
+        &block,
+        &owner,
+        allowance,
+    )?))
+}
+
+#[cfg(feature = "debug")]
+fn query_raw_state<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    prefix: String,
+    start_after: Option<Binary>,
+    limit: u32,
+) -> StdResult<crate::msg::RawStateResponse> {
+    use cosmwasm_std::{Order, ReadonlyStorage};
+    use cosmwasm_storage::ReadonlyPrefixedStorage;
+
+    use crate::msg::{RawEntry, RawStateResponse};
+
+    let limit = limit.min(MAX_LIMIT) as usize;
+    let start = start_after.map(|key| [key.as_slice(), &[0]].concat());
+
+    let storage = ReadonlyPrefixedStorage::new(prefix.as_bytes(), &deps.storage);
+    let entries = storage
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|(key, value)| RawEntry {
+            key: Binary(key),
+            value: Binary(value),
+        })
+        .collect();
+    Ok(RawStateResponse { entries })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{coins, from_binary};
+
+    const INITIAL_TOTAL_SUPPLY: u128 = 100_000_000;
+    const INITIAL_BALANCE: u128 = 1_000_000;
+    const ALLOWANCE_AMOUNT: u128 = 10_000;
+    const TOTAL_SUPPLY: u128 = INITIAL_TOTAL_SUPPLY + INITIAL_BALANCE;
+
+    fn init_msg() -> InitMsg {
+        InitMsg {
+            name: "Craboken".into(),
+            symbol: "CRAB".into(),
+            decimals: 6,
+            minter: "minter".into(),
+            total_supply: Some(Uint128(INITIAL_TOTAL_SUPPLY)),
+            cap: None,
+            marketing: None,
+            initial_balances: vec![InitBalance {
+                address: "treasury".into(),
+                amount: Uint128(INITIAL_TOTAL_SUPPLY),
+            }],
+            init_recipient: None,
+            fee_bps: None,
+            fee_collector: None,
+            admin: None,
+            max_mint_per_tx: None,
+            min_transfer_amount: None,
+            burnable_by_holders: None,
+            default_allowance_ttl: None,
+            initial_frozen: vec![],
+            fee_rounding: Rounding::Down,
+            burn_fee: Uint128(0),
+        }
+    }
+
+    fn init_contract<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
+        let msg = init_msg();
+
+        let env = mock_env("creator", &[]);
+
+        let _res = init(deps, env, msg).unwrap();
+    }
+
+    fn mint<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(INITIAL_BALANCE),
+        };
+
+        let env = mock_env("minter", &[]);
+
+        handle(deps, env, msg).unwrap();
+    }
+
+    fn set_allowance<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
+        let msg = HandleMsg::SetAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
+            is_allowed: true,
+            expires: None,
+        };
+
+        let env = mock_env("sender", &[]);
+
+        handle(deps, env, msg).unwrap();
+    }
+
+    // lays storage out the way the original release did: no version, a two-field state,
+    // zero balances kept around and allowances without a spender index
+    fn seed_baseline<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
+        use cosmwasm_storage::{to_length_prefixed, PrefixedStorage};
+
+        let state = format!(
+            r#"{{"minter":"minter","total_supply":"{}"}}"#,
+            INITIAL_BALANCE
+        );
+        deps.storage
+            .set(&to_length_prefixed(b"state"), state.as_bytes());
+
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let drained = deps.api.canonical_address(&"drained".into()).unwrap();
+        let spender = deps.api.canonical_address(&"third_party".into()).unwrap();
+        let mut balances = PrefixedStorage::new(b"balances", &mut deps.storage);
+        balances.set(
+            sender.as_slice(),
+            format!(r#""{}""#, INITIAL_BALANCE).as_bytes(),
+        );
+        balances.set(drained.as_slice(), br#""0""#);
+
+        let allowance = format!(r#"{{"is_allowed":true,"amount":"{}"}}"#, ALLOWANCE_AMOUNT);
+        PrefixedStorage::multilevel(&[b"allowances", sender.as_slice()], &mut deps.storage)
+            .set(spender.as_slice(), allowance.as_bytes());
+    }
+
+    fn baseline_migrate_msg() -> MigrateMsg {
+        MigrateMsg {
+            token_info: Some(TokenMetadata {
+                name: "Craboken".into(),
+                symbol: "CRAB".into(),
+                decimals: 6,
+            }),
+        }
+    }
+
+    #[test]
+    fn proper_init() {
+        let mut deps = mock_dependencies(16, &[]);
+        init_contract(&mut deps);
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.name, "Craboken");
+        assert_eq!(state.symbol, "CRAB");
+        assert_eq!(state.decimals, 6);
+    }
+
+    #[test]
+    fn init_symbol_too_long() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            symbol: "CRABOKENTOKEN".into(),
+            ..init_msg()
+        };
+
+        let env = mock_env("creator", &[]);
+
+        let err = init(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Ticker symbol must be 3-12 alphanumeric characters")
+        );
+    }
+
+    #[test]
+    fn init_too_many_decimals() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            decimals: 200,
+            ..init_msg()
+        };
+
+        let env = mock_env("creator", &[]);
+
+        let err = init(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Decimals must not exceed 18"));
+    }
+
+    #[test]
+    fn handle_mint() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply.u128(), TOTAL_SUPPLY);
+    }
+
+    #[test]
+    fn handle_mint_log() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(1000),
+        };
+
+        let env = mock_env("minter", &[]);
+
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(
+            res.log,
+            vec![
+                log("action", "mint"),
+                log("to", "sender"),
+                log("amount", "1000"),
+                log("new_total_supply", INITIAL_TOTAL_SUPPLY + 1000),
+            ]
+        );
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply, Uint128(INITIAL_TOTAL_SUPPLY + 1000));
+    }
+
+    #[test]
+    fn handle_mint_unauthorized() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(1000),
+        };
+
+        let env = mock_env("not_minter", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("only minter minter may mint"));
+    }
+
+    #[test]
+    fn handle_mint_too_many() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(u128::MAX),
+        };
+
+        let env = mock_env("minter", &[]);
+
+        handle(&mut deps, env, msg).unwrap_err();
+    }
+
+    #[test]
+    fn handle_transfer() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let sender_env = mock_env("sender", &[]);
+
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(1000),
+        };
+
+        let res = handle(&mut deps, sender_env, msg).unwrap();
+        assert_eq!(
+            res.log,
+            vec![
+                log("action", "transfer"),
+                log("from", "sender"),
+                log("to", "recipient"),
+                log("amount", "1000"),
+                log("from_balance", INITIAL_BALANCE - 1000),
+                log("to_balance", "1000"),
+            ]
+        );
+
+        let resp = query(
+            &deps,
+            QueryMsg::GetBalance {
+                user: "sender".into(),
+            },
+        )
+        .unwrap();
+        let resp: BalanceResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.amount.to_string(), res.log[4].value);
+
+        let resp = query(
+            &deps,
+            QueryMsg::GetBalance {
+                user: "recipient".into(),
+            },
+        )
+        .unwrap();
+        let resp: BalanceResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.amount.to_string(), res.log[5].value);
+
+        let sender = deps
+            .api
+            .canonical_address(&HumanAddr::from("sender"))
+            .unwrap();
+        let recipient = deps
+            .api
+            .canonical_address(&HumanAddr::from("recipient"))
+            .unwrap();
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+
+        let sender_balance = balances.get(&sender).unwrap();
+        assert_eq!(sender_balance, INITIAL_BALANCE - 1000);
+
+        let recipient_balance = balances.get(&recipient).unwrap();
+        assert_eq!(recipient_balance, 1000);
+    }
+
+    #[test]
+    fn handle_transfer_to_self() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let sender_env = mock_env("sender", &[]);
+
+        let msg = HandleMsg::Transfer {
+            to: "sender".into(),
+            amount: Uint128(1000),
+        };
+
+        handle(&mut deps, sender_env, msg).unwrap();
+
+        let sender = deps
+            .api
+            .canonical_address(&HumanAddr::from("sender"))
+            .unwrap();
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        let sender_balance = balances.get(&sender).unwrap();
+        assert_eq!(sender_balance, INITIAL_BALANCE);
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply.u128(), TOTAL_SUPPLY);
+    }
+
+    #[test]
+    fn handle_burn() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let sender_env = mock_env("sender", &[]);
+
+        let msg = HandleMsg::Burn {
+            amount: Uint128(1000),
+        };
+
+        let res = handle(&mut deps, sender_env, msg).unwrap();
+        assert_eq!(
+            res.log,
+            vec![
+                log("action", "burn"),
+                log("from", "sender"),
+                log("amount", "1000"),
+            ]
+        );
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+
+        let sender = deps
+            .api
+            .canonical_address(&HumanAddr::from("sender"))
+            .unwrap();
+        let sender_balance = balances.get(&sender).unwrap();
+        assert_eq!(sender_balance, INITIAL_BALANCE - 1000);
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply.u128(), TOTAL_SUPPLY - 1000);
+    }
+
+    #[test]
+    fn handle_burn_more_than_total_supply() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        State::write(&mut deps.storage)
+            .update(|mut state| {
+                state.total_supply = Uint128(0);
+                Ok(state)
+            })
+            .unwrap();
+
+        let sender_env = mock_env("sender", &[]);
+
+        let msg = HandleMsg::Burn {
+            amount: Uint128(1000),
+        };
+
+        handle(&mut deps, sender_env, msg).unwrap_err();
+    }
+
+    #[test]
+    fn handle_set_allowance() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+
+        let owner = deps
+            .api
+            .canonical_address(&HumanAddr::from("sender"))
+            .unwrap();
+        let third_party = deps
+            .api
+            .canonical_address(&HumanAddr::from("third_party"))
+            .unwrap();
+
+        let allowances = ReadOnlyAllowances::new(&owner, &deps.storage);
+        let allowance = allowances.get(&third_party).unwrap().unwrap();
+        assert!(allowance.is_allowed);
+        assert_eq!(allowance.amount.u128(), ALLOWANCE_AMOUNT);
+    }
+
+    #[test]
+    fn handle_transfer_from() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+
+        let third_party_env = mock_env("third_party", &[]);
+
+        let msg = HandleMsg::TransferFrom {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(1000),
+        };
+
+        let res = handle(&mut deps, third_party_env, msg).unwrap();
+        assert_eq!(
+            res.log,
+            vec![
+                log("action", "transfer_from"),
+                log("from", "sender"),
+                log("to", "recipient"),
+                log("by", "third_party"),
+                log("amount", "1000"),
+                log("remaining_allowance", ALLOWANCE_AMOUNT - 1000),
+            ]
+        );
+
+        let sender = deps
+            .api
+            .canonical_address(&HumanAddr::from("sender"))
+            .unwrap();
+        let recipient = deps
+            .api
+            .canonical_address(&HumanAddr::from("recipient"))
+            .unwrap();
+        let third_party = deps
+            .api
+            .canonical_address(&HumanAddr::from("third_party"))
+            .unwrap();
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        let recipient_balance = balances.get(&recipient).unwrap();
+        assert_eq!(recipient_balance, 1000);
+
+        let allowances = ReadOnlyAllowances::new(&sender, &deps.storage);
+        let allowance = allowances.get(&third_party).unwrap().unwrap();
+        assert_eq!(allowance.amount.u128(), ALLOWANCE_AMOUNT - 1000);
+    }
+
+    #[test]
+    fn handle_transfer_from_too_many() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+
+        let third_party_env = mock_env("third_party", &[]);
+
+        let msg = HandleMsg::TransferFrom {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT * 2),
+        };
+
+        handle(&mut deps, third_party_env, msg).unwrap_err();
+    }
+
+    #[test]
+    fn handle_transfer_from_unauthorized() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let third_party_env = mock_env("third_party", &[]);
+
+        let msg = HandleMsg::TransferFrom {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(1000),
+        };
+
+        let err = handle(&mut deps, third_party_env, msg).unwrap_err();
+        assert_eq!(err, StdError::unauthorized());
+    }
+
+    #[test]
+    fn handle_transfer_from_allowance_is_false() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        // set allowance
+        let msg = HandleMsg::SetAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
+            is_allowed: false,
+            expires: None,
+        };
+
+        let env = mock_env("sender", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        // transfer from
+        let third_party_env = mock_env("third_party", &[]);
+
+        let msg = HandleMsg::TransferFrom {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(1000),
+        };
+
+        let err = handle(&mut deps, third_party_env, msg).unwrap_err();
+        assert_eq!(err, StdError::unauthorized());
+    }
+
+    #[test]
+    fn handle_burn_from() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+
+        let third_party_env = mock_env("third_party", &[]);
+
+        let msg = HandleMsg::BurnFrom {
+            from: "sender".into(),
+            amount: Uint128(1000),
+        };
+
+        let res = handle(&mut deps, third_party_env, msg).unwrap();
+        assert_eq!(
+            res.log,
+            vec![
+                log("action", "burn_from"),
+                log("from", "sender"),
+                log("by", "third_party"),
+                log("amount", "1000"),
+                log("remaining_allowance", ALLOWANCE_AMOUNT - 1000),
+            ]
+        );
+
+        let sender = deps
+            .api
+            .canonical_address(&HumanAddr::from("sender"))
+            .unwrap();
+        let third_party = deps
+            .api
+            .canonical_address(&HumanAddr::from("third_party"))
+            .unwrap();
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        let sender_balance = balances.get(&sender).unwrap();
+        assert_eq!(sender_balance, INITIAL_BALANCE - 1000);
+
+        let allowances = ReadOnlyAllowances::new(&sender, &deps.storage);
+        let allowance = allowances.get(&third_party).unwrap().unwrap();
+        assert_eq!(allowance.amount.u128(), ALLOWANCE_AMOUNT - 1000);
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply.u128(), TOTAL_SUPPLY - 1000);
+    }
+
+    #[test]
+    fn handle_burn_from_too_many() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+
+        let third_party_env = mock_env("third_party", &[]);
+
+        let msg = HandleMsg::BurnFrom {
+            from: "sender".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT * 2),
+        };
+
+        handle(&mut deps, third_party_env, msg).unwrap_err();
+    }
+
+    #[test]
+    fn handle_burn_from_unauthorized() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let third_party_env = mock_env("third_party", &[]);
+
+        let msg = HandleMsg::BurnFrom {
+            from: "sender".into(),
+            amount: Uint128(1000),
+        };
+
+        let err = handle(&mut deps, third_party_env, msg).unwrap_err();
+        assert_eq!(err, StdError::unauthorized());
+    }
+
+    #[test]
+    fn handle_burn_from_allowance_is_false() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        // set allowance
+        let msg = HandleMsg::SetAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
+            is_allowed: false,
+            expires: None,
+        };
+
+        let env = mock_env("sender", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        // burn from
+        let third_party_env = mock_env("third_party", &[]);
+
+        let msg = HandleMsg::BurnFrom {
+            from: "sender".into(),
+            amount: Uint128(1000),
+        };
+
+        let err = handle(&mut deps, third_party_env, msg).unwrap_err();
+        assert_eq!(err, StdError::unauthorized());
+    }
+
+    #[test]
+    fn query_get_balance() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let msg = QueryMsg::GetBalance {
+            user: "sender".into(),
+        };
+
+        let resp = query(&mut deps, msg).unwrap();
+        let resp: BalanceResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.amount.u128(), INITIAL_BALANCE);
+    }
+
+    #[test]
+    fn query_token_info_metadata() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = QueryMsg::TokenInfo {};
+
+        let resp = query(&mut deps, msg).unwrap();
+        let resp: TokenInfoResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.name, "Craboken");
+        assert_eq!(resp.symbol, "CRAB");
+        assert_eq!(resp.decimals, 6);
+        assert_eq!(resp.total_supply.u128(), INITIAL_TOTAL_SUPPLY);
+    }
+
+    #[test]
+    fn query_allowance_existing() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+
+        let msg = QueryMsg::Allowance {
+            owner: "sender".into(),
+            spender: "third_party".into(),
+            block: None,
+        };
+
+        let resp = query(&mut deps, msg).unwrap();
+        let resp: AllowanceResponse = from_binary(&resp).unwrap();
+        assert!(resp.is_allowed);
+        assert_eq!(resp.amount.u128(), ALLOWANCE_AMOUNT);
+    }
+
+    #[test]
+    fn query_allowance_missing() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = QueryMsg::Allowance {
+            owner: "sender".into(),
+            spender: "third_party".into(),
+            block: None,
+        };
+
+        let resp = query(&mut deps, msg).unwrap();
+        let resp: AllowanceResponse = from_binary(&resp).unwrap();
+        assert!(!resp.is_allowed);
+        assert_eq!(resp.amount.u128(), 0);
+    }
+
+    #[test]
+    fn handle_increase_allowance() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        for _ in 0..2 {
+            let msg = HandleMsg::IncreaseAllowance {
+                spender: "third_party".into(),
+                amount: Uint128(ALLOWANCE_AMOUNT),
+                expires: None,
+            };
+
+            let env = mock_env("sender", &[]);
+
+            handle(&mut deps, env, msg).unwrap();
+        }
+
+        let owner = deps
+            .api
+            .canonical_address(&HumanAddr::from("sender"))
+            .unwrap();
+        let third_party = deps
+            .api
+            .canonical_address(&HumanAddr::from("third_party"))
+            .unwrap();
+
+        let allowances = ReadOnlyAllowances::new(&owner, &deps.storage);
+        let allowance = allowances.get(&third_party).unwrap().unwrap();
+        assert!(allowance.is_allowed);
+        assert_eq!(allowance.amount.u128(), ALLOWANCE_AMOUNT * 2);
+    }
+
+    #[test]
+    fn handle_decrease_allowance_below_zero() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+
+        let msg = HandleMsg::DecreaseAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT * 2),
+        };
+
+        let env = mock_env("sender", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let owner = deps
+            .api
+            .canonical_address(&HumanAddr::from("sender"))
+            .unwrap();
+        let third_party = deps
+            .api
+            .canonical_address(&HumanAddr::from("third_party"))
+            .unwrap();
+
+        let allowances = ReadOnlyAllowances::new(&owner, &deps.storage);
+        assert_eq!(allowances.get(&third_party).unwrap(), None);
+    }
+
+    #[test]
+    fn handle_zero_amount() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+
+        let cases = vec![
+            (
+                "sender",
+                HandleMsg::Transfer {
+                    to: "recipient".into(),
+                    amount: Uint128(0),
+                },
+            ),
+            (
+                "third_party",
+                HandleMsg::TransferFrom {
+                    from: "sender".into(),
+                    to: "recipient".into(),
+                    amount: Uint128(0),
+                },
+            ),
+            ("sender", HandleMsg::Burn { amount: Uint128(0) }),
+            (
+                "third_party",
+                HandleMsg::BurnFrom {
+                    from: "sender".into(),
+                    amount: Uint128(0),
+                },
+            ),
+            (
+                "minter",
+                HandleMsg::Mint {
+                    recipient: "sender".into(),
+                    amount: Uint128(0),
+                },
+            ),
+        ];
+
+        for (sender, msg) in cases {
+            let env = mock_env(sender, &[]);
+
+            let err = handle(&mut deps, env, msg).unwrap_err();
+            assert_eq!(
+                err,
+                StdError::generic_err("amount must be greater than zero")
+            );
+        }
+    }
+
+    fn set_paused<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>, paused: bool) {
+        let msg = HandleMsg::SetPaused { paused };
+
+        let env = mock_env("minter", &[]);
+
+        handle(deps, env, msg).unwrap();
+    }
+
+    #[test]
+    fn handle_set_paused_unauthorized() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::SetPaused { paused: true };
+
+        let env = mock_env("not_minter", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("only admin minter may pause"));
+    }
+
+    #[test]
+    fn handle_transfer_paused() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+        set_paused(&mut deps, true);
+
+        let cases = vec![
+            (
+                "sender",
+                HandleMsg::Transfer {
+                    to: "recipient".into(),
+                    amount: Uint128(1000),
+                },
+            ),
+            (
+                "third_party",
+                HandleMsg::TransferFrom {
+                    from: "sender".into(),
+                    to: "recipient".into(),
+                    amount: Uint128(1000),
+                },
+            ),
+            (
+                "sender",
+                HandleMsg::Burn {
+                    amount: Uint128(1000),
+                },
+            ),
+            (
+                "third_party",
+                HandleMsg::BurnFrom {
+                    from: "sender".into(),
+                    amount: Uint128(1000),
+                },
+            ),
+        ];
+
+        for (sender, msg) in cases {
+            let env = mock_env(sender, &[]);
+
+            let err = handle(&mut deps, env, msg).unwrap_err();
+            assert_eq!(err, ContractError::Paused.into());
+        }
+
+        // minting is still allowed
+        mint(&mut deps);
+    }
+
+    #[test]
+    fn handle_transfer_unpaused() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_paused(&mut deps, true);
+        set_paused(&mut deps, false);
+
+        let sender_env = mock_env("sender", &[]);
+
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(1000),
+        };
+
+        handle(&mut deps, sender_env, msg).unwrap();
+    }
+
+    #[test]
+    fn handle_update_minter() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::UpdateMinter {
+            new_minter: Some("new_minter".into()),
+        };
+
+        let env = mock_env("minter", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let minters = ReadOnlyMinters::new(&deps.storage);
+        let minter_addr = deps.api.canonical_address(&"minter".into()).unwrap();
+        let new_minter_addr = deps.api.canonical_address(&"new_minter".into()).unwrap();
+        assert!(!minters.contains(&minter_addr));
+        assert!(minters.contains(&new_minter_addr));
+
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(INITIAL_BALANCE),
+        };
+
+        let env = mock_env("minter", &[]);
+
+        let err = handle(&mut deps, env, msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("only minter new_minter may mint")
+        );
+
+        let env = mock_env("new_minter", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+    }
+
+    #[test]
+    fn handle_update_minter_unauthorized() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::UpdateMinter {
+            new_minter: Some("not_minter".into()),
+        };
+
+        let env = mock_env("not_minter", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("only minter minter may update minters")
+        );
+    }
+
+    #[test]
+    fn handle_update_minter_cleared() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::UpdateMinter { new_minter: None };
+
+        let env = mock_env("minter", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(INITIAL_BALANCE),
+        };
+
+        let env = mock_env("minter", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("no minter is set, so nobody may mint")
+        );
+    }
+
+    #[test]
+    fn handle_add_minter() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::AddMinter {
+            minter: "second_minter".into(),
+        };
+
+        let env = mock_env("minter", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(INITIAL_BALANCE),
+        };
+
+        let env = mock_env("second_minter", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let balance = ReadOnlyBalances::new(&deps.storage).get(&sender).unwrap();
+        assert_eq!(balance, INITIAL_BALANCE);
+    }
+
+    #[test]
+    fn handle_add_minter_unauthorized() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::AddMinter {
+            minter: "not_minter".into(),
+        };
+
+        let env = mock_env("not_minter", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("only minter minter may add minters")
+        );
+    }
+
+    #[test]
+    fn handle_remove_minter() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::AddMinter {
+            minter: "second_minter".into(),
+        };
+
+        let env = mock_env("minter", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let msg = HandleMsg::RemoveMinter {
+            minter: "second_minter".into(),
+        };
+
+        let env = mock_env("minter", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(INITIAL_BALANCE),
+        };
+
+        let env = mock_env("second_minter", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("only minter minter may mint"));
+    }
+
+    #[test]
+    fn handle_mint_up_to_cap() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            cap: Some(Uint128(INITIAL_TOTAL_SUPPLY + INITIAL_BALANCE)),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        mint(&mut deps);
+
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(1),
+        };
+
+        let env = mock_env("minter", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("minting would exceed cap"));
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(
+            state.total_supply,
+            Uint128(INITIAL_TOTAL_SUPPLY + INITIAL_BALANCE)
+        );
+    }
+
+    #[test]
+    fn init_supply_exceeds_cap() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            cap: Some(Uint128(INITIAL_TOTAL_SUPPLY - 1)),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+
+        let err = init(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Initial supply exceeds cap"));
+    }
+
+    #[test]
+    fn query_minter_cap() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            cap: Some(Uint128(INITIAL_TOTAL_SUPPLY)),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        let resp = query(&deps, QueryMsg::Minter {}).unwrap();
+        let resp: MinterResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.cap, Some(Uint128(INITIAL_TOTAL_SUPPLY)));
+    }
+
+    #[test]
+    fn handle_send() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let msg = HandleMsg::Send {
+            contract: "receiver".into(),
+            code_hash: "receiver_hash".into(),
+            amount: Uint128(1000),
+            msg: Binary::from(b"payload".to_vec()),
+        };
+
+        let env = mock_env("sender", &[]);
+
+        let resp = handle(&mut deps, env, msg).unwrap();
+
+        let receive_msg = ReceiverHandleMsg::Receive {
+            sender: "sender".into(),
+            amount: Uint128(1000),
+            msg: Binary::from(b"payload".to_vec()),
+        };
+        assert_eq!(
+            resp.messages,
+            vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "receiver".into(),
+                callback_code_hash: "receiver_hash".into(),
+                msg: to_binary(&receive_msg).unwrap(),
+                send: vec![],
+            })]
+        );
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let receiver = deps.api.canonical_address(&"receiver".into()).unwrap();
+        assert_eq!(balances.get(&sender).unwrap(), INITIAL_BALANCE - 1000);
+        assert_eq!(balances.get(&receiver).unwrap(), 1000);
+    }
+
+    #[test]
+    fn handle_batch_transfer() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let msg = HandleMsg::BatchTransfer {
+            transfers: vec![
+                TransferAction {
+                    to: "first".into(),
+                    amount: Uint128(100),
+                },
+                TransferAction {
+                    to: "second".into(),
+                    amount: Uint128(200),
+                },
+                TransferAction {
+                    to: "third".into(),
+                    amount: Uint128(300),
+                },
+            ],
+        };
+
+        let env = mock_env("sender", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        for (user, amount) in &[
+            ("sender", INITIAL_BALANCE - 600),
+            ("first", 100),
+            ("second", 200),
+            ("third", 300),
+        ] {
+            let user = deps.api.canonical_address(&(*user).into()).unwrap();
+            assert_eq!(balances.get(&user).unwrap(), *amount);
+        }
+    }
+
+    #[test]
+    fn handle_batch_transfer_overdraw() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let msg = HandleMsg::BatchTransfer {
+            transfers: vec![
+                TransferAction {
+                    to: "first".into(),
+                    amount: Uint128(100),
+                },
+                TransferAction {
+                    to: "second".into(),
+                    amount: Uint128(INITIAL_BALANCE),
+                },
+            ],
+        };
+
+        let env = mock_env("sender", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientFunds {
+                balance: INITIAL_BALANCE,
+                required: INITIAL_BALANCE + 100,
+            }
+            .into()
+        );
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let first = deps.api.canonical_address(&"first".into()).unwrap();
+        assert_eq!(balances.get(&sender).unwrap(), INITIAL_BALANCE);
+        assert_eq!(balances.get(&first).unwrap(), 0);
+    }
+
+    #[test]
+    fn handle_batch_transfer_too_long() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let transfer = TransferAction {
+            to: "recipient".into(),
+            amount: Uint128(1),
+        };
+        let msg = HandleMsg::BatchTransfer {
+            transfers: vec![transfer; MAX_BATCH_TRANSFERS + 1],
+        };
+
+        let env = mock_env("sender", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Batch must not contain more than 256 transfers")
+        );
+    }
+
+    #[test]
+    fn query_all_accounts_paging() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let users = ["alice", "bob", "carol", "dave", "erin"];
+        for user in &users {
+            let msg = HandleMsg::Mint {
+                recipient: (*user).into(),
+                amount: Uint128(INITIAL_BALANCE),
+            };
+
+            let env = mock_env("minter", &[]);
+
+            handle(&mut deps, env, msg).unwrap();
+        }
+
+        let mut accounts: Vec<HumanAddr> = vec![];
+        let mut start_after = None;
+        loop {
+            let msg = QueryMsg::AllAccounts {
+                start_after: start_after.clone(),
+                limit: Some(2),
+            };
+
+            let resp = query(&deps, msg).unwrap();
+            let resp: AllAccountsResponse = from_binary(&resp).unwrap();
+            assert!(resp.accounts.len() <= 2);
+            if resp.accounts.is_empty() {
+                break;
+            }
+
+            start_after = resp.accounts.last().cloned();
+            accounts.extend(resp.accounts);
+        }
+
+        let mut expected: Vec<HumanAddr> = users.iter().map(|user| (*user).into()).collect();
+        expected.push("treasury".into());
+        accounts.sort();
+        expected.sort();
+        assert_eq!(accounts, expected);
+    }
+
+    #[test]
+    fn query_all_allowances_paging() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let spenders = ["alice", "bob", "carol"];
+        for (i, spender) in spenders.iter().enumerate() {
+            let msg = HandleMsg::SetAllowance {
+                spender: (*spender).into(),
+                amount: Uint128(ALLOWANCE_AMOUNT * (i as u128 + 1)),
+                is_allowed: true,
+                expires: None,
+            };
+
+            let env = mock_env("sender", &[]);
+
+            handle(&mut deps, env, msg).unwrap();
+        }
+
+        let msg = QueryMsg::AllAllowances {
+            owner: "sender".into(),
+            start_after: None,
+            limit: Some(2),
+        };
+
+        let resp = query(&deps, msg).unwrap();
+        let first_page: AllAllowancesResponse = from_binary(&resp).unwrap();
+        assert_eq!(first_page.allowances.len(), 2);
+
+        let msg = QueryMsg::AllAllowances {
+            owner: "sender".into(),
+            start_after: Some(first_page.allowances[1].spender.clone()),
+            limit: Some(2),
+        };
+
+        let resp = query(&deps, msg).unwrap();
+        let second_page: AllAllowancesResponse = from_binary(&resp).unwrap();
+        assert_eq!(second_page.allowances.len(), 1);
+
+        let mut allowances: Vec<AllowanceInfo> = first_page
+            .allowances
+            .into_iter()
+            .chain(second_page.allowances)
+            .collect();
+        allowances.sort_by(|a, b| a.spender.as_str().cmp(b.spender.as_str()));
+
+        let expected: Vec<AllowanceInfo> = spenders
+            .iter()
+            .enumerate()
+            .map(|(i, spender)| AllowanceInfo {
+                spender: (*spender).into(),
+                amount: Uint128(ALLOWANCE_AMOUNT * (i as u128 + 1)),
+                is_allowed: true,
+                expires: Expiration::Never,
+            })
+            .collect();
+        assert_eq!(allowances, expected);
+    }
+
+    fn assert_supply_invariant<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>) {
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        let mut held = 0u128;
+        let mut start_after = None;
+        loop {
+            let page = balances
+                .range(start_after.as_ref(), MAX_LIMIT as usize)
+                .unwrap();
+            held += page.iter().map(|(_, amount)| amount).sum::<u128>();
+            match page.last() {
+                Some((addr, _)) => start_after = Some(addr.clone()),
+                None => break,
+            }
+        }
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply.u128(), held);
+    }
+
+    fn init_with_marketing<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
+        let msg = InitMsg {
+            marketing: Some("marketing".into()),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(deps, env, msg).unwrap();
+    }
+
+    #[test]
+    fn handle_upload_logo_embedded() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_with_marketing(&mut deps);
+
+        let png = Binary::from([PNG_HEADER, b"image data"].concat());
+        let msg = HandleMsg::UploadLogo {
+            logo: Logo::Embedded(png.clone()),
+        };
+
+        let env = mock_env("marketing", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let resp = query(&deps, QueryMsg::MarketingInfo {}).unwrap();
+        let resp: MarketingInfoResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.logo, Some(LogoInfo::Embedded));
+        assert_eq!(resp.marketing, Some("marketing".into()));
+
+        let resp = query(&deps, QueryMsg::DownloadLogo {}).unwrap();
+        let resp: DownloadLogoResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            resp,
+            DownloadLogoResponse {
+                mime_type: "image/png".into(),
+                data: png,
+            }
+        );
+    }
+
+    #[test]
+    fn handle_upload_logo_invalid() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_with_marketing(&mut deps);
+
+        let msg = HandleMsg::UploadLogo {
+            logo: Logo::Embedded(Binary::from(b"not an image".to_vec())),
+        };
+
+        let env = mock_env("marketing", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Logo must be a PNG or SVG image")
+        );
+    }
+
+    #[test]
+    fn handle_upload_logo_unauthorized() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_with_marketing(&mut deps);
+
+        let msg = HandleMsg::UploadLogo {
+            logo: Logo::Url("https://example.com/logo.png".into()),
+        };
+
+        let env = mock_env("not_marketing", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::unauthorized());
+    }
+
+    #[test]
+    fn handle_update_marketing() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_with_marketing(&mut deps);
+
+        let msg = HandleMsg::UpdateMarketing {
+            project: Some("https://craboken.example".into()),
+            description: Some("A crab themed token".into()),
+            marketing: None,
+        };
+
+        let env = mock_env("marketing", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let resp = query(&deps, QueryMsg::MarketingInfo {}).unwrap();
+        let resp: MarketingInfoResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            resp,
+            MarketingInfoResponse {
+                project: Some("https://craboken.example".into()),
+                description: Some("A crab themed token".into()),
+                marketing: Some("marketing".into()),
+                logo: None,
+            }
+        );
+    }
+
+    #[test]
+    fn migrate_bumps_version() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        seed_baseline(&mut deps);
+        assert_eq!(
+            ContractVersion::read(&deps.storage).may_load().unwrap(),
+            None
+        );
+
+        let env = mock_env("creator", &[]);
+        migrate(&mut deps, env, baseline_migrate_msg()).unwrap();
+
+        let version = ContractVersion::read(&deps.storage).load().unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // a second run finds the version and refuses
+        let env = mock_env("creator", &[]);
+        migrate(&mut deps, env, baseline_migrate_msg()).unwrap_err();
+    }
+
+    #[test]
+    fn migrate_fills_in_later_state_fields() {
+        use cosmwasm_std::ReadonlyStorage;
+        use cosmwasm_storage::to_length_prefixed;
+
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        // an earlier release, whose State had none of the fields added since
+        ContractVersion::write(&mut deps.storage)
+            .save(&ContractVersion {
+                contract: CONTRACT_NAME.into(),
+                version: "0.0.1".into(),
+            })
+            .unwrap();
+        let stored = format!(
+            r#"{{"name":"Craboken","symbol":"CRAB","decimals":6,"total_supply":"{}",
+            "cap":null,"paused":false,"fee_bps":0,"fee_collector":"minter","admin":"minter",
+            "max_mint_per_tx":null,"min_transfer_amount":null,"default_allowance_ttl":null,
+            "daily_transfer_limit":null}}"#,
+            TOTAL_SUPPLY
+        );
+        deps.storage
+            .set(&to_length_prefixed(b"state"), stored.as_bytes());
+
+        migrate(&mut deps, mock_env("creator", &[]), MigrateMsg::default()).unwrap();
+
+        let raw = deps.storage.get(&to_length_prefixed(b"state")).unwrap();
+        let raw = String::from_utf8(raw).unwrap();
+        for field in &[
+            "fee_rounding",
+            "burnable_by_holders",
+            "burn_address",
+            "burn_fee",
+        ] {
+            assert!(raw.contains(field), "{} was not written back", field);
+        }
+        let state = State::read(&deps.storage).load().unwrap();
+        let contract = deps
+            .api
+            .canonical_address(&mock_env("creator", &[]).contract.address);
+        assert_eq!(
+            state.burn_address,
+            CanonicalAddr::from(vec![0; contract.unwrap().len()])
+        );
+
+        let msg = HandleMsg::SendToBurnAddress {
+            amount: Uint128(1000),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        let resp = query(&deps, QueryMsg::BurnedBalance {}).unwrap();
+        let resp: BalanceResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.amount, Uint128(1000));
+        assert_supply_invariant(&deps);
+    }
+
+    #[test]
+    fn migrate_same_version() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let env = mock_env("creator", &[]);
+        let err = migrate(&mut deps, env, MigrateMsg::default()).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(format!(
+                "Cannot migrate from version {0} to {0}",
+                CONTRACT_VERSION
+            ))
+        );
+    }
+
+    #[test]
+    fn migrate_other_contract() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        ContractVersion::write(&mut deps.storage)
+            .save(&ContractVersion {
+                contract: "other".into(),
+                version: "0.0.1".into(),
+            })
+            .unwrap();
+
+        let env = mock_env("creator", &[]);
+        let err = migrate(&mut deps, env, MigrateMsg::default()).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Cannot migrate from a different contract: other")
+        );
+    }
+
+    #[test]
+    fn init_initial_balances() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            initial_balances: vec![
+                InitBalance {
+                    address: "alice".into(),
+                    amount: Uint128(1000),
+                },
+                InitBalance {
+                    address: "bob".into(),
+                    amount: Uint128(2000),
+                },
+            ],
+            total_supply: None,
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        let alice = deps.api.canonical_address(&"alice".into()).unwrap();
+        let bob = deps.api.canonical_address(&"bob".into()).unwrap();
+        assert_eq!(balances.get(&alice).unwrap(), 1000);
+        assert_eq!(balances.get(&bob).unwrap(), 2000);
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply, Uint128(3000));
+    }
+
+    #[test]
+    fn init_initial_balances_duplicate() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            initial_balances: vec![
+                InitBalance {
+                    address: "alice".into(),
+                    amount: Uint128(1000),
+                },
+                InitBalance {
+                    address: "alice".into(),
+                    amount: Uint128(2000),
+                },
+            ],
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+
+        let err = init(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Duplicate address in initial balances: alice")
+        );
+    }
+
+    #[test]
+    fn init_declared_total_supply_matches() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            total_supply: Some(Uint128(3000)),
+            initial_balances: vec![
+                InitBalance {
+                    address: "alice".into(),
+                    amount: Uint128(1000),
+                },
+                InitBalance {
+                    address: "bob".into(),
+                    amount: Uint128(2000),
+                },
+            ],
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply, Uint128(3000));
+    }
+
+    #[test]
+    fn init_declared_total_supply_mismatch() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            total_supply: Some(Uint128(INITIAL_TOTAL_SUPPLY + 1)),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+
+        let err = init(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("declared total supply does not match initial balances")
+        );
+    }
+
+    fn set_frozen<S: Storage, A: Api, Q: Querier>(
+        deps: &mut Extern<S, A, Q>,
+        address: &str,
+        frozen: bool,
+    ) {
+        let msg = if frozen {
+            HandleMsg::Freeze {
+                address: address.into(),
+            }
+        } else {
+            HandleMsg::Unfreeze {
+                address: address.into(),
+            }
+        };
+
+        let env = mock_env("minter", &[]);
+
+        handle(deps, env, msg).unwrap();
+    }
+
+    #[test]
+    fn handle_transfer_frozen() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_frozen(&mut deps, "sender", true);
+
+        let resp = query(
+            &deps,
+            QueryMsg::IsFrozen {
+                address: "sender".into(),
+            },
+        )
+        .unwrap();
+        let resp: IsFrozenResponse = from_binary(&resp).unwrap();
+        assert!(resp.frozen);
+
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(1000),
+        };
+
+        let env = mock_env("sender", &[]);
+
+        let err = handle(&mut deps, env, msg.clone()).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Sender account is frozen"));
+
+        set_frozen(&mut deps, "sender", false);
+
+        let env = mock_env("sender", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+    }
+
+    #[test]
+    fn handle_transfer_to_frozen() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_frozen(&mut deps, "recipient", true);
+
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(1000),
+        };
+
+        let env = mock_env("sender", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Recipient account is frozen"));
+    }
+
+    #[test]
+    fn handle_freeze_unauthorized() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::Freeze {
+            address: "sender".into(),
+        };
+
+        let env = mock_env("not_minter", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("only admin minter may freeze accounts")
+        );
+    }
+
+    #[test]
+    fn handle_burn_all() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let total_supply = State::read(&deps.storage).load().unwrap().total_supply;
+
+        let env = mock_env("sender", &[]);
+
+        let resp = handle(&mut deps, env, HandleMsg::BurnAll {}).unwrap();
+        assert_eq!(
+            resp.log,
+            vec![
+                log("action", "burn"),
+                log("from", "sender"),
+                log("amount", INITIAL_BALANCE),
+            ]
+        );
+
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let balance = ReadOnlyBalances::new(&deps.storage).get(&sender).unwrap();
+        assert_eq!(balance, 0);
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(
+            state.total_supply.u128(),
+            total_supply.u128() - INITIAL_BALANCE
+        );
+
+        let env = mock_env("sender", &[]);
+
+        let err = handle(&mut deps, env, HandleMsg::BurnAll {}).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("amount must be greater than zero")
+        );
+    }
+
+    #[test]
+    fn query_formatted_balance_display() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            total_supply: None,
+            initial_balances: vec![InitBalance {
+                address: "holder".into(),
+                amount: Uint128(1_500_000),
+            }],
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        for (user, raw, display) in &[("holder", 1_500_000, "1.5"), ("nobody", 0, "0")] {
+            let msg = QueryMsg::FormattedBalance {
+                user: (*user).into(),
+            };
+
+            let resp = query(&deps, msg).unwrap();
+            let resp: FormattedBalanceResponse = from_binary(&resp).unwrap();
+            assert_eq!(
+                resp,
+                FormattedBalanceResponse {
+                    raw: Uint128(*raw),
+                    display: (*display).into(),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn format_amount_fractions() {
+        assert_eq!(format_amount(1_000_000, 6), "1");
+        assert_eq!(format_amount(1_000_001, 6), "1.000001");
+        assert_eq!(format_amount(10, 6), "0.00001");
+        assert_eq!(format_amount(42, 0), "42");
+    }
+
+    #[test]
+    fn init_recipient_credited() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            total_supply: Some(Uint128(INITIAL_TOTAL_SUPPLY)),
+            initial_balances: vec![InitBalance {
+                address: "alice".into(),
+                amount: Uint128(1000),
+            }],
+            init_recipient: Some("creator".into()),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        let creator = deps.api.canonical_address(&"creator".into()).unwrap();
+        let alice = deps.api.canonical_address(&"alice".into()).unwrap();
+        let creator_balance = balances.get(&creator).unwrap();
+        let alice_balance = balances.get(&alice).unwrap();
+        assert_eq!(creator_balance, INITIAL_TOTAL_SUPPLY);
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply.u128(), creator_balance + alice_balance);
+    }
+
+    #[test]
+    fn init_recipient_without_supply() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            total_supply: None,
+            init_recipient: Some("creator".into()),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+
+        let err = init(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("init_recipient requires a non-zero total_supply")
+        );
+    }
+
+    #[test]
+    fn init_without_recipient_unbacked_supply() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            total_supply: Some(Uint128(INITIAL_TOTAL_SUPPLY)),
+            initial_balances: vec![],
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+
+        let err = init(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("declared total supply does not match initial balances")
+        );
+
+        let msg = InitMsg {
+            total_supply: None,
+            initial_balances: vec![],
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply, Uint128(0));
+    }
+
+    #[test]
+    fn handle_transfer_from_insufficient_allowance() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+
+        let msg = HandleMsg::TransferFrom {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT + 1),
+        };
+
+        let env = mock_env("third_party", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientAllowance.into());
+    }
+
+    #[test]
+    fn handle_transfer_from_insufficient_balance() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        set_allowance(&mut deps);
+
+        let msg = HandleMsg::TransferFrom {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
+        };
+
+        let env = mock_env("third_party", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientFunds {
+                balance: 0,
+                required: ALLOWANCE_AMOUNT,
+            }
+            .into()
+        );
+
+        let owner = deps.api.canonical_address(&"sender".into()).unwrap();
+        let spender = deps.api.canonical_address(&"third_party".into()).unwrap();
+        let allowance = ReadOnlyAllowances::new(&owner, &deps.storage)
+            .get(&spender)
+            .unwrap()
+            .unwrap();
+        assert_eq!(allowance.amount, Uint128(ALLOWANCE_AMOUNT));
+    }
+
+    #[test]
+    fn handle_transfer_with_fee() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            fee_bps: Some(100),
+            fee_collector: Some("collector".into()),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+        mint(&mut deps);
+
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(10_000),
+        };
+
+        let env = mock_env("sender", &[]);
+
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(res.log.last(), Some(&log("fee", "100")));
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        for (user, amount) in &[
+            ("sender", INITIAL_BALANCE - 10_000),
+            ("recipient", 9_900),
+            ("collector", 100),
+        ] {
+            let user = deps.api.canonical_address(&(*user).into()).unwrap();
+            assert_eq!(balances.get(&user).unwrap(), *amount);
+        }
+    }
+
+    #[test]
+    fn handle_update_fee() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::UpdateFee {
+            fee_bps: MAX_FEE_BPS + 1,
+            fee_collector: "collector".into(),
+            fee_rounding: None,
+        };
+
+        let env = mock_env("minter", &[]);
+
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Fee must not exceed 1000 bps"));
+
+        let msg = HandleMsg::UpdateFee {
+            fee_bps: 50,
+            fee_collector: "collector".into(),
+            fee_rounding: Some(Rounding::Up),
+        };
+
+        let env = mock_env("not_minter", &[]);
+
+        let err = handle(&mut deps, env, msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("only admin minter may update the fee")
+        );
+
+        let env = mock_env("minter", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.fee_bps, 50);
+        assert_eq!(state.fee_rounding, Rounding::Up);
+        assert_eq!(state.fee_collector, HumanAddr::from("collector"));
+    }
+
+    #[test]
+    fn compute_fee_rounding() {
+        assert_eq!(compute_fee(10_000, 100, Rounding::Down), 100);
+        assert_eq!(compute_fee(99, 100, Rounding::Down), 0);
+        assert_eq!(compute_fee(12_345, 0, Rounding::Down), 0);
+        assert_eq!(compute_fee(u128::MAX, 1000, Rounding::Down), u128::MAX / 10);
+    }
+
+    #[test]
+    fn compute_fee_rounding_modes() {
+        // (amount, fee_bps, down, up, nearest)
+        for &(amount, fee_bps, down, up, nearest) in &[
+            // no remainder
+            (10_000, 100, 100, 100, 100),
+            (0, 100, 0, 0, 0),
+            // smallest remainder
+            (1, 1, 0, 1, 0),
+            // just below, exactly at and just above half
+            (49, 100, 0, 1, 0),
+            (50, 100, 0, 1, 0),
+            (51, 100, 0, 1, 1),
+            // exactly half rounds to the even fee
+            (150, 100, 1, 2, 2),
+            (250, 100, 2, 3, 2),
+            // largest remainder
+            (9_999, 1, 0, 1, 1),
+            (
+                u128::MAX,
+                1000,
+                u128::MAX / 10,
+                u128::MAX / 10 + 1,
+                u128::MAX / 10 + 1,
+            ),
+        ] {
+            assert_eq!(compute_fee(amount, fee_bps, Rounding::Down), down);
+            assert_eq!(compute_fee(amount, fee_bps, Rounding::Up), up);
+            assert_eq!(compute_fee(amount, fee_bps, Rounding::Nearest), nearest);
+        }
+    }
+
+    #[test]
+    fn query_balances_ordered() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let msg = QueryMsg::Balances {
+            users: vec!["treasury".into(), "nobody".into(), "sender".into()],
+        };
+
+        let resp = query(&deps, msg).unwrap();
+        let resp: Vec<BalanceResponse> = from_binary(&resp).unwrap();
+        assert_eq!(
+            resp,
+            vec![
+                BalanceResponse {
+                    amount: Uint128(INITIAL_TOTAL_SUPPLY),
+                },
+                BalanceResponse { amount: Uint128(0) },
+                BalanceResponse {
+                    amount: Uint128(INITIAL_BALANCE),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn query_balances_too_many() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = QueryMsg::Balances {
+            users: vec!["sender".into(); MAX_BALANCES_QUERY + 1],
+        };
+
+        let err = query(&deps, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Cannot query more than 50 balances at once")
+        );
+    }
+
+    #[test]
+    fn handle_set_allowance_self() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msgs = vec![
+            HandleMsg::SetAllowance {
+                spender: "sender".into(),
+                amount: Uint128(ALLOWANCE_AMOUNT),
+                is_allowed: true,
+                expires: None,
+            },
+            HandleMsg::IncreaseAllowance {
+                spender: "sender".into(),
+                amount: Uint128(ALLOWANCE_AMOUNT),
+                expires: None,
+            },
+            HandleMsg::DecreaseAllowance {
+                spender: "sender".into(),
+                amount: Uint128(ALLOWANCE_AMOUNT),
+            },
+        ];
+
+        for msg in msgs {
+            let env = mock_env("sender", &[]);
+
+            let err = handle(&mut deps, env, msg).unwrap_err();
+            assert_eq!(
+                err,
+                StdError::generic_err("cannot set allowance for yourself")
+            );
+        }
+
+        set_allowance(&mut deps);
+    }
+
+    #[test]
+    fn handle_revoke_allowance() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        set_allowance(&mut deps);
+
+        let msg = HandleMsg::RevokeAllowance {
+            spender: "third_party".into(),
+        };
+
+        let env = mock_env("sender", &[]);
+
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(
+            res.log,
+            vec![
+                log(ACTION, "revoke_allowance"),
+                log(OWNER, "sender"),
+                log(SPENDER, "third_party"),
+                log(AMOUNT, 0),
+                log("is_allowed", false),
+            ]
+        );
+
+        let msg = QueryMsg::Allowance {
+            owner: "sender".into(),
+            spender: "third_party".into(),
+            block: None,
+        };
+
+        let resp = query(&deps, msg).unwrap();
+        let resp: AllowanceResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            resp,
+            AllowanceResponse {
+                amount: Uint128(0),
+                is_allowed: false,
+                expires: Expiration::Never,
+                is_expired: false,
+            }
+        );
+
+        let owner = deps.api.canonical_address(&"sender".into()).unwrap();
+        let spender = deps.api.canonical_address(&"third_party".into()).unwrap();
+        let allowance = ReadOnlyAllowances::new(&owner, &deps.storage)
+            .get(&spender)
+            .unwrap();
+        assert!(allowance.is_none());
+    }
+
+    fn init_with_admin<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
+        let msg = InitMsg {
+            admin: Some("admin".into()),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(deps, env, msg).unwrap();
+    }
+
+    #[test]
+    fn handle_set_paused_by_admin() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_with_admin(&mut deps);
+
+        let resp = query(&deps, QueryMsg::Admin {}).unwrap();
+        let resp: AdminResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.admin, HumanAddr::from("admin"));
+
+        let msg = HandleMsg::SetPaused { paused: true };
+
+        let env = mock_env("minter", &[]);
+
+        let err = handle(&mut deps, env, msg.clone()).unwrap_err();
+        assert_eq!(err, StdError::generic_err("only admin admin may pause"));
+
+        let env = mock_env("admin", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert!(state.paused);
+    }
+
+    #[test]
+    fn handle_update_admin() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_with_admin(&mut deps);
+
+        let msg = HandleMsg::UpdateAdmin {
+            new_admin: "new_admin".into(),
+        };
+
+        let env = mock_env("not_admin", &[]);
+
+        let err = handle(&mut deps, env, msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("only admin admin may update the admin")
+        );
+
+        let env = mock_env("admin", &[]);
+
+        handle(&mut deps, env, msg).unwrap();
+
+        let resp = query(&deps, QueryMsg::Admin {}).unwrap();
+        let resp: AdminResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.admin, HumanAddr::from("new_admin"));
+    }
+
+    #[test]
+    fn query_balance_at_snapshot() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("minter", &[]);
+        let snapshot_height = env.block.height;
+        let resp = handle(&mut deps, env, HandleMsg::Snapshot {}).unwrap();
+        assert_eq!(
+            resp.log,
+            vec![log("action", "snapshot"), log("height", snapshot_height)]
+        );
+
+        let mut env = mock_env("sender", &[]);
+        env.block.height += 1;
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(INITIAL_BALANCE),
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let msg = QueryMsg::BalanceAt {
+            user: "sender".into(),
+            height: snapshot_height,
+        };
+        let resp: BalanceResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp.amount, Uint128(INITIAL_BALANCE));
+
+        let msg = QueryMsg::BalanceAt {
+            user: "recipient".into(),
+            height: snapshot_height + 10,
+        };
+        let resp: BalanceResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp.amount, Uint128(0));
+
+        let msg = QueryMsg::GetBalance {
+            user: "sender".into(),
+        };
+        let resp: BalanceResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp.amount, Uint128(0));
+
+        let msg = QueryMsg::BalanceAt {
+            user: "sender".into(),
+            height: snapshot_height - 1,
+        };
+        let err = query(&deps, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(format!(
+                "No snapshot taken at or before height {}",
+                snapshot_height - 1
+            ))
+        );
+    }
+
+    #[test]
+    fn handle_snapshot_unauthorized() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let err = handle(&mut deps, env, HandleMsg::Snapshot {}).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("only admin minter may take snapshots")
+        );
+    }
+
+    #[test]
+    fn handle_transfer_all() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::TransferAll {
+            to: "recipient".into(),
+        };
+        let resp = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(
+            resp.log,
+            vec![
+                log("action", "transfer_all"),
+                log("from", "sender"),
+                log("to", "recipient"),
+                log("amount", INITIAL_BALANCE),
+            ]
+        );
+
+        let msg = QueryMsg::GetBalance {
+            user: "sender".into(),
+        };
+        let resp: BalanceResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp.amount, Uint128(0));
+
+        let msg = QueryMsg::GetBalance {
+            user: "recipient".into(),
+        };
+        let resp: BalanceResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp.amount, Uint128(INITIAL_BALANCE));
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::TransferAll {
+            to: "recipient".into(),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("amount must be greater than zero")
+        );
+    }
+
+    #[test]
+    fn init_msg_validate() {
+        assert_eq!(init_msg().validate(), Ok(()));
+
+        let msg = InitMsg {
+            minter: "".into(),
+            ..init_msg()
+        };
+        assert_eq!(
+            msg.validate(),
+            Err(StdError::generic_err("Minter address must not be empty"))
+        );
+    }
+
+    #[test]
+    fn init_empty_minter() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            minter: "".into(),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        let err = init(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Minter address must not be empty")
+        );
+    }
+
+    #[test]
+    fn query_version() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let resp = query(&deps, QueryMsg::Version {}).unwrap();
+        let resp: VersionResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            resp,
+            VersionResponse {
+                contract: CONTRACT_NAME.to_string(),
+                version: CONTRACT_VERSION.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn query_version_not_stored() {
+        let deps = mock_dependencies(16, &[]);
+
+        let resp = query(&deps, QueryMsg::Version {}).unwrap();
+        let resp: VersionResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.version, "0");
+    }
+
+    #[test]
+    fn total_supply_covers_balances_after_mint_and_burn() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("treasury", &[]);
+        let msg = HandleMsg::Burn {
+            amount: Uint128(INITIAL_TOTAL_SUPPLY / 2),
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::BurnAll {};
+        handle(&mut deps, env, msg).unwrap();
+
+        assert_supply_invariant(&deps);
+    }
+
+    #[test]
+    fn burn_exceeding_total_supply() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        State::write(&mut deps.storage)
+            .update(|mut state| {
+                state.total_supply = Uint128(1);
+                Ok(state)
+            })
+            .unwrap();
+
+        let env = mock_env("treasury", &[]);
+        let msg = HandleMsg::Burn { amount: Uint128(2) };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("burn amount exceeds total supply")
+        );
+    }
+
+    #[test]
+    fn allowances_for_owner_matches_canonical() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let owner: HumanAddr = "owner".into();
+        let spender = deps.api.canonical_address(&"spender".into()).unwrap();
+        let allowance = Allowance {
+            is_allowed: true,
+            amount: Uint128(42),
+            expires: Expiration::Never,
+            ..Allowance::default()
+        };
+
+        let mut allowances = Allowances::for_owner(&deps.api, &owner, &mut deps.storage).unwrap();
+        allowances.set(&spender, allowance.clone()).unwrap();
+
+        let owner_addr = deps.api.canonical_address(&owner).unwrap();
+        let allowances = ReadOnlyAllowances::new(&owner_addr, &deps.storage);
+        assert_eq!(allowances.get(&spender).unwrap(), Some(allowance));
+    }
+
+    #[test]
+    fn handle_transfer_with_memo() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::TransferWithMemo {
+            to: "recipient".into(),
+            amount: Uint128(INITIAL_BALANCE),
+            memo: "deposit-1234".into(),
+        };
+        let resp = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(resp.log.last(), Some(&log("memo", "deposit-1234")));
+
+        let msg = QueryMsg::GetBalance {
+            user: "recipient".into(),
+        };
+        let resp: BalanceResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp.amount, Uint128(INITIAL_BALANCE));
+    }
+
+    #[test]
+    fn handle_transfer_with_memo_too_long() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::TransferWithMemo {
+            to: "recipient".into(),
+            amount: Uint128(INITIAL_BALANCE),
+            memo: "x".repeat(MAX_MEMO_LEN + 1),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Memo must not exceed 256 bytes"));
+    }
+
+    #[test]
+    fn handle_prune_allowance() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let expires_at = env.block.height + 1;
+        let msg = HandleMsg::SetAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
+            is_allowed: true,
+            expires: Some(Expiration::AtHeight(expires_at)),
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let prune = HandleMsg::PruneAllowance {
+            owner: "sender".into(),
+            spender: "third_party".into(),
+        };
+
+        let env = mock_env("keeper", &[]);
+        let err = handle(&mut deps, env, prune.clone()).unwrap_err();
+        assert_eq!(err, StdError::generic_err("allowance has not expired"));
+
+        let mut env = mock_env("third_party", &[]);
+        env.block.height = expires_at;
+        let msg = HandleMsg::TransferFrom {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(1),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("allowance expired"));
+
+        let mut env = mock_env("keeper", &[]);
+        env.block.height = expires_at;
+        let resp = handle(&mut deps, env, prune).unwrap();
+        assert_eq!(
+            resp.log,
+            vec![
+                log("action", "prune_allowance"),
+                log("owner", "sender"),
+                log("spender", "third_party"),
+            ]
+        );
+
+        let owner = deps.api.canonical_address(&"sender".into()).unwrap();
+        let spender = deps.api.canonical_address(&"third_party".into()).unwrap();
+        let allowances = ReadOnlyAllowances::new(&owner, &deps.storage);
+        assert_eq!(allowances.get(&spender).unwrap(), None);
+    }
+
+    #[test]
+    fn handle_mint_per_tx_limit() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            max_mint_per_tx: Some(Uint128(INITIAL_BALANCE)),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        mint(&mut deps);
+
+        let env = mock_env("minter", &[]);
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(INITIAL_BALANCE + 1),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("mint exceeds per-tx limit"));
+    }
+
+    #[test]
+    fn handle_update_mint_limit() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::UpdateMintLimit {
+            max_mint_per_tx: Some(Uint128(1)),
+        };
+        let env = mock_env("sender", &[]);
+        let err = handle(&mut deps, env, msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("only admin minter may update the mint limit")
+        );
+
+        let env = mock_env("minter", &[]);
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("minter", &[]);
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(2),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("mint exceeds per-tx limit"));
+    }
+
+    #[test]
+    fn handle_transfer_insufficient_funds() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(INITIAL_BALANCE + 1),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientFunds {
+                balance: INITIAL_BALANCE,
+                required: INITIAL_BALANCE + 1,
+            }
+            .into()
+        );
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Burn {
+            amount: Uint128(INITIAL_BALANCE + 1),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientFunds {
+                balance: INITIAL_BALANCE,
+                required: INITIAL_BALANCE + 1,
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn handle_mint_overflow() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let env = mock_env("minter", &[]);
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(u128::MAX),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, ContractError::Overflow.into());
+    }
+
+    #[test]
+    fn contract_error_into_std_error() {
+        assert_eq!(
+            StdError::from(ContractError::Unauthorized),
+            StdError::unauthorized()
+        );
+        assert_eq!(
+            StdError::from(ContractError::Paused),
+            StdError::generic_err("contract is paused")
+        );
+        assert_eq!(
+            StdError::from(ContractError::InsufficientFunds {
+                balance: 1,
+                required: 2,
+            }),
+            StdError::generic_err("insufficient funds: balance 1, required 2")
+        );
+        assert_eq!(
+            StdError::from(ContractError::Underflow),
+            StdError::generic_err("Amount underflow")
+        );
+    }
+
+    #[test]
+    fn query_balance_and_supply() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let msg = QueryMsg::BalanceAndSupply {
+            user: "sender".into(),
+        };
+        let resp: BalanceAndSupplyResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(
+            resp,
+            BalanceAndSupplyResponse {
+                balance: Uint128(INITIAL_BALANCE),
+                total_supply: Uint128(INITIAL_TOTAL_SUPPLY + INITIAL_BALANCE),
+            }
+        );
+    }
+
+    #[test]
+    fn handle_decrease_allowance_to_zero_removes_it() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+
+        let msg = HandleMsg::DecreaseAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
+        };
+        let env = mock_env("sender", &[]);
+        handle(&mut deps, env, msg).unwrap();
+
+        let owner = deps.api.canonical_address(&"sender".into()).unwrap();
+        let third_party = deps.api.canonical_address(&"third_party".into()).unwrap();
+        let allowances = ReadOnlyAllowances::new(&owner, &deps.storage);
+        assert_eq!(allowances.get(&third_party).unwrap(), None);
+        assert!(allowances.range(None, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn handle_batch_mint() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::BatchMint {
+            mints: vec![
+                MintAction {
+                    recipient: "first".into(),
+                    amount: Uint128(100),
+                },
+                MintAction {
+                    recipient: "second".into(),
+                    amount: Uint128(200),
+                },
+            ],
+        };
+        let env = mock_env("minter", &[]);
+        let resp = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(
+            resp.log,
+            vec![
+                log("action", "batch_mint"),
+                log("amount", 300),
+                log("new_total_supply", INITIAL_TOTAL_SUPPLY + 300),
+            ]
+        );
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        for (user, amount) in &[("first", 100), ("second", 200)] {
+            let user = deps.api.canonical_address(&(*user).into()).unwrap();
+            assert_eq!(balances.get(&user).unwrap(), *amount);
+        }
+    }
+
+    #[test]
+    fn handle_batch_mint_exceeds_cap() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            cap: Some(Uint128(INITIAL_TOTAL_SUPPLY + 250)),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        let msg = HandleMsg::BatchMint {
+            mints: vec![
+                MintAction {
+                    recipient: "first".into(),
+                    amount: Uint128(100),
+                },
+                MintAction {
+                    recipient: "second".into(),
+                    amount: Uint128(200),
+                },
+            ],
+        };
+        let env = mock_env("minter", &[]);
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("minting would exceed cap"));
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        let first = deps.api.canonical_address(&"first".into()).unwrap();
+        assert_eq!(balances.get(&first).unwrap(), 0);
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply, Uint128(INITIAL_TOTAL_SUPPLY));
+    }
+
+    #[test]
+    fn burn_entire_balance_removes_account() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Burn {
+            amount: Uint128(INITIAL_BALANCE),
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let msg = QueryMsg::AllAccounts {
+            start_after: None,
+            limit: None,
+        };
+        let resp: AllAccountsResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp.accounts, vec![HumanAddr::from("treasury")]);
+
+        let msg = QueryMsg::GetBalance {
+            user: "sender".into(),
+        };
+        let resp: BalanceResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp.amount, Uint128(0));
+    }
+
+    #[test]
+    fn handle_transfer_min_amount() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            min_transfer_amount: Some(Uint128(10)),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+        mint(&mut deps);
+
+        let resp = query(&deps, QueryMsg::MinTransferAmount {}).unwrap();
+        let resp: MinTransferAmountResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.min_transfer_amount, Some(Uint128(10)));
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(9),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Transfer amount must be at least 10")
+        );
+
+        set_allowance(&mut deps);
+        let env = mock_env("third_party", &[]);
+        let msg = HandleMsg::TransferFrom {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(9),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Transfer amount must be at least 10")
+        );
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(10),
+        };
+        handle(&mut deps, env, msg).unwrap();
+    }
+
+    #[test]
+    fn send_receiver_can_call_back() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Send {
+            contract: "receiver".into(),
+            code_hash: "hash".into(),
+            amount: Uint128(1),
+            msg: Binary::from(b"{}".to_vec()),
+        };
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        // the Receive callback runs once the send has been applied, so it can spend what arrived
+        let env = mock_env("receiver", &[]);
+        let msg = HandleMsg::Transfer {
+            to: "sender".into(),
+            amount: Uint128(1),
+        };
+        handle(&mut deps, env, msg).unwrap();
+    }
+
+    #[test]
+    fn query_spendable_allowance_capped_by_balance() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let block = env.block.clone();
+        let msg = HandleMsg::SetAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(INITIAL_BALANCE * 2),
+            is_allowed: true,
+            expires: Some(Expiration::AtHeight(block.height + 10)),
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let msg = QueryMsg::SpendableAllowance {
+            owner: "sender".into(),
+            spender: "third_party".into(),
+            block: block.clone(),
+        };
+        let resp: Uint128 = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp, Uint128(INITIAL_BALANCE));
+
+        let msg = QueryMsg::SpendableAllowance {
+            owner: "sender".into(),
+            spender: "third_party".into(),
+            block: BlockInfo {
+                height: block.height + 10,
+                ..block
+            },
+        };
+        let resp: Uint128 = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp, Uint128(0));
+    }
+
+    #[test]
+    fn malformed_amount_is_parse_error() {
+        for amount in &["", "-1", "1.5", "abc"] {
+            let raw = format!(
+                r#"{{"transfer":{{"to":"recipient","amount":"{}"}}}}"#,
+                amount
+            );
+            let err = from_binary::<HandleMsg>(&Binary::from(raw.into_bytes())).unwrap_err();
+            match err {
+                StdError::ParseErr { .. } => {}
+                err => panic!("unexpected error for {:?}: {:?}", amount, err),
+            }
+        }
+    }
+
+    #[test]
+    fn handle_force_transfer_from_frozen() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_frozen(&mut deps, "sender", true);
+
+        let msg = HandleMsg::ForceTransfer {
+            from: "sender".into(),
+            to: "treasury".into(),
+            amount: Uint128(INITIAL_BALANCE),
+        };
+
+        let env = mock_env("sender", &[]);
+        let err = handle(&mut deps, env, msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("only admin minter may force transfers")
+        );
+
+        let env = mock_env("minter", &[]);
+        let resp = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(
+            resp.log,
+            vec![
+                log("action", "force_transfer"),
+                log("from", "sender"),
+                log("to", "treasury"),
+                log("amount", INITIAL_BALANCE),
+            ]
+        );
+
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let treasury = deps.api.canonical_address(&"treasury".into()).unwrap();
+        assert_eq!(balances.get(&sender).unwrap(), 0);
+        assert_eq!(
+            balances.get(&treasury).unwrap(),
+            INITIAL_TOTAL_SUPPLY + INITIAL_BALANCE
+        );
+    }
+
+    #[test]
+    fn handle_force_transfer_from_unfrozen() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("minter", &[]);
+        let msg = HandleMsg::ForceTransfer {
+            from: "sender".into(),
+            to: "treasury".into(),
+            amount: Uint128(INITIAL_BALANCE),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Force transfer requires a frozen account")
+        );
+    }
+
+    #[test]
+    fn invariant_after_operations() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        assert_supply_invariant(&deps);
+
+        mint(&mut deps);
+        assert_supply_invariant(&deps);
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(100),
+        };
+        handle(&mut deps, env, msg).unwrap();
+        assert_supply_invariant(&deps);
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Transfer {
+            to: "sender".into(),
+            amount: Uint128(100),
+        };
+        handle(&mut deps, env, msg).unwrap();
+        assert_supply_invariant(&deps);
+
+        let env = mock_env("recipient", &[]);
+        let msg = HandleMsg::Burn {
+            amount: Uint128(50),
+        };
+        handle(&mut deps, env, msg).unwrap();
+        assert_supply_invariant(&deps);
+
+        set_allowance(&mut deps);
+        let env = mock_env("third_party", &[]);
+        let msg = HandleMsg::TransferFrom {
+            from: "sender".into(),
+            to: "third_party".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
+        };
+        handle(&mut deps, env, msg).unwrap();
+        assert_supply_invariant(&deps);
+    }
+
+    #[test]
+    fn handle_mint_once_rejects_replay() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::MintOnce {
+            recipient: "sender".into(),
+            amount: Uint128(INITIAL_BALANCE),
+            nonce: "bridge-1".into(),
+        };
+
+        let env = mock_env("sender", &[]);
+        let err = handle(&mut deps, env, msg.clone()).unwrap_err();
+        assert_eq!(err, StdError::generic_err("only minter minter may mint"));
+
+        let env = mock_env("minter", &[]);
+        let resp = handle(&mut deps, env, msg.clone()).unwrap();
+        assert_eq!(resp.log.last(), Some(&log("nonce", "bridge-1")));
+
+        let env = mock_env("minter", &[]);
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("duplicate mint"));
+
+        let msg = QueryMsg::GetBalance {
+            user: "sender".into(),
+        };
+        let resp: BalanceResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp.amount, Uint128(INITIAL_BALANCE));
+    }
+
+    #[test]
+    fn query_holder_count() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let count = |deps: &Extern<_, _, _>| -> u64 {
+            from_binary(&query(deps, QueryMsg::HolderCount {}).unwrap()).unwrap()
+        };
+
+        init_contract(&mut deps);
+        assert_eq!(count(&deps), 1);
+
+        mint(&mut deps);
+        mint(&mut deps);
+        assert_eq!(count(&deps), 2);
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(100),
+        };
+        handle(&mut deps, env, msg).unwrap();
+        assert_eq!(count(&deps), 3);
+
+        let env = mock_env("recipient", &[]);
+        handle(&mut deps, env, HandleMsg::BurnAll {}).unwrap();
+        assert_eq!(count(&deps), 2);
+
+        let env = mock_env("sender", &[]);
+        handle(&mut deps, env, HandleMsg::BurnAll {}).unwrap();
+        assert_eq!(count(&deps), 1);
+    }
+
+    #[test]
+    fn handle_burn_by_holders_disabled() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            burnable_by_holders: Some(false),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+        mint(&mut deps);
+
+        let burn = HandleMsg::Burn { amount: Uint128(1) };
+
+        let env = mock_env("sender", &[]);
+        let err = handle(&mut deps, env, burn.clone()).unwrap_err();
+        assert_eq!(err, StdError::unauthorized());
+
+        let env = mock_env("sender", &[]);
+        let err = handle(&mut deps, env, HandleMsg::BurnAll {}).unwrap_err();
+        assert_eq!(err, StdError::unauthorized());
+
+        set_allowance(&mut deps);
+        let env = mock_env("third_party", &[]);
+        let msg = HandleMsg::BurnFrom {
+            from: "sender".into(),
+            amount: Uint128(1),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, StdError::unauthorized());
+
+        let env = mock_env("minter", &[]);
+        let msg = HandleMsg::Mint {
+            recipient: "minter".into(),
+            amount: Uint128(1),
+        };
+        handle(&mut deps, env, msg).unwrap();
+        let env = mock_env("minter", &[]);
+        handle(&mut deps, env, burn).unwrap();
+    }
+
+    #[test]
+    fn handle_burn_by_holders_default() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert!(state.burnable_by_holders);
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Burn { amount: Uint128(1) };
+        handle(&mut deps, env, msg).unwrap();
+    }
+
+    #[test]
+    fn query_all_minters() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        for minter in &["alice", "bob"] {
+            let env = mock_env("minter", &[]);
+            let msg = HandleMsg::AddMinter {
+                minter: (*minter).into(),
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+
+        let resp = query(&deps, QueryMsg::AllMinters {}).unwrap();
+        let mut minters: Vec<HumanAddr> = from_binary(&resp).unwrap();
+        minters.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        assert_eq!(
+            minters,
+            vec![
+                HumanAddr::from("alice"),
+                HumanAddr::from("bob"),
+                HumanAddr::from("minter"),
+            ]
+        );
+    }
+
+    #[test]
+    fn handle_transfer_to_existing() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::TransferToExisting {
+            to: "treasury".into(),
+            amount: Uint128(100),
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::TransferToExisting {
+            to: "typo".into(),
+            amount: Uint128(100),
+        };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Recipient account does not exist")
+        );
+    }
+
+    #[test]
+    fn handle_allowance_default_ttl() {
+        let mut deps = mock_dependencies(16, &[]);
 
-    fn init_contract<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
         let msg = InitMsg {
-            minter: "minter".into(),
-            total_supply: Uint128(INITIAL_TOTAL_SUPPLY),
+            default_allowance_ttl: Some(3600),
+            ..init_msg()
+        };
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("sender", &[]);
+        let now = env.block.time;
+        let msg = HandleMsg::SetAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
+            is_allowed: true,
+            expires: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::IncreaseAllowance {
+            spender: "recipient".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
+            expires: Some(Expiration::AtHeight(100)),
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let owner = deps.api.canonical_address(&"sender".into()).unwrap();
+        let allowances = ReadOnlyAllowances::new(&owner, &deps.storage);
+
+        let third_party = deps.api.canonical_address(&"third_party".into()).unwrap();
+        let allowance = allowances.get(&third_party).unwrap().unwrap();
+        assert_eq!(allowance.expires, Expiration::AtTime(now + 3600));
+
+        let recipient = deps.api.canonical_address(&"recipient".into()).unwrap();
+        let allowance = allowances.get(&recipient).unwrap().unwrap();
+        assert_eq!(allowance.expires, Expiration::AtHeight(100));
+    }
+
+    #[cfg(feature = "debug")]
+    #[test]
+    fn query_raw_state_balances() {
+        use crate::msg::RawStateResponse;
+
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let msg = QueryMsg::RawState {
+            prefix: "balances".into(),
+            start_after: None,
+            limit: 10,
+        };
+        let resp: RawStateResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp.entries.len(), 2);
+
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let entry = resp
+            .entries
+            .iter()
+            .find(|entry| entry.key.as_slice() == sender.as_slice())
+            .unwrap();
+        let amount: Uint128 = from_binary(&entry.value).unwrap();
+        assert_eq!(amount, Uint128(INITIAL_BALANCE));
+    }
+
+    #[test]
+    fn handle_transfer_to_token_contract() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Transfer {
+            to: env.contract.address.clone(),
+            amount: Uint128(1000),
+        };
+        let resp = handle(&mut deps, env, msg);
+        assert_eq!(
+            resp,
+            Err(StdError::generic_err(
+                "cannot transfer to the token contract"
+            ))
+        );
+
+        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Send {
+            contract: env.contract.address.clone(),
+            code_hash: "token_hash".into(),
+            amount: Uint128(1000),
+            msg: Binary::from(b"payload".to_vec()),
+        };
+        let resp = handle(&mut deps, env, msg);
+        assert_eq!(
+            resp,
+            Err(StdError::generic_err(
+                "cannot transfer to the token contract"
+            ))
+        );
+
+        set_allowance(&mut deps);
+        let token = mock_env("sender", &[]).contract.address;
+        let cases = vec![
+            (
+                "sender",
+                HandleMsg::BatchTransfer {
+                    transfers: vec![TransferAction {
+                        to: token.clone(),
+                        amount: Uint128(1000),
+                    }],
+                },
+            ),
+            ("sender", HandleMsg::TransferAll { to: token.clone() }),
+            (
+                "third_party",
+                HandleMsg::TransferFrom {
+                    from: "sender".into(),
+                    to: token.clone(),
+                    amount: Uint128(1000),
+                },
+            ),
+            (
+                "third_party",
+                HandleMsg::TransferFromPartial {
+                    from: "sender".into(),
+                    to: token,
+                    amount: Uint128(1000),
+                },
+            ),
+        ];
+        for (signer, msg) in cases {
+            let resp = handle(&mut deps, mock_env(signer, &[]), msg);
+            assert_eq!(
+                resp,
+                Err(StdError::generic_err(
+                    "cannot transfer to the token contract"
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn handle_transfer_from_partial_allowance_limited() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+
+        let msg = HandleMsg::TransferFromPartial {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT * 2),
+        };
+        let res = handle(&mut deps, mock_env("third_party", &[]), msg).unwrap();
+        assert_eq!(res.log[4], log("amount", ALLOWANCE_AMOUNT));
+        assert_eq!(res.log[5], log("remaining_allowance", 0));
+
+        let recipient = deps
+            .api
+            .canonical_address(&HumanAddr::from("recipient"))
+            .unwrap();
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        assert_eq!(balances.get(&recipient).unwrap(), ALLOWANCE_AMOUNT);
+    }
+
+    #[test]
+    fn handle_transfer_from_partial_balance_limited() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        set_allowance(&mut deps);
+
+        let msg = HandleMsg::Transfer {
+            to: "other".into(),
+            amount: Uint128(INITIAL_BALANCE - 500),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+
+        let msg = HandleMsg::TransferFromPartial {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(5000),
+        };
+        let res = handle(&mut deps, mock_env("third_party", &[]), msg).unwrap();
+        assert_eq!(res.log[4], log("amount", 500));
+        assert_eq!(
+            res.log[5],
+            log("remaining_allowance", ALLOWANCE_AMOUNT - 500)
+        );
+
+        let sender = deps
+            .api
+            .canonical_address(&HumanAddr::from("sender"))
+            .unwrap();
+        let recipient = deps
+            .api
+            .canonical_address(&HumanAddr::from("recipient"))
+            .unwrap();
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        assert_eq!(balances.get(&sender).unwrap(), 0);
+        assert_eq!(balances.get(&recipient).unwrap(), 500);
+    }
+
+    #[test]
+    fn cw20_attribute_keys() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let keys = |resp: &HandleResponse| {
+            resp.log
+                .iter()
+                .map(|attr| attr.key.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(INITIAL_BALANCE),
+        };
+        let resp = handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+        assert_eq!(resp.log[0], log("action", "mint"));
+        assert_eq!(keys(&resp)[..3], ["action", "to", "amount"]);
+
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(1000),
+        };
+        let resp = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        assert_eq!(resp.log[0], log("action", "transfer"));
+        assert_eq!(keys(&resp)[..4], ["action", "from", "to", "amount"]);
+
+        let msg = HandleMsg::Burn {
+            amount: Uint128(1000),
+        };
+        let resp = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        assert_eq!(resp.log[0], log("action", "burn"));
+        assert_eq!(keys(&resp), ["action", "from", "amount"]);
+    }
+
+    #[test]
+    fn query_allowance_height_expiry() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let expires = Expiration::AtHeight(env.block.height + 10);
+        let msg = HandleMsg::SetAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
+            is_allowed: true,
+            expires: Some(expires.clone()),
+        };
+        handle(&mut deps, env.clone(), msg).unwrap();
+
+        let query_at = |height: u64| {
+            let msg = QueryMsg::Allowance {
+                owner: "sender".into(),
+                spender: "third_party".into(),
+                block: Some(BlockInfo {
+                    height,
+                    ..env.block.clone()
+                }),
+            };
+            let resp: AllowanceResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+            resp
+        };
+
+        let resp = query_at(env.block.height + 5);
+        assert_eq!(resp.expires, expires);
+        assert!(!resp.is_expired);
+
+        let resp = query_at(env.block.height + 10);
+        assert_eq!(resp.expires, expires);
+        assert!(resp.is_expired);
+    }
+
+    #[test]
+    fn init_initial_balances_overflow() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            total_supply: None,
+            initial_balances: vec![
+                InitBalance {
+                    address: "treasury".into(),
+                    amount: Uint128(u128::MAX / 2 + 1),
+                },
+                InitBalance {
+                    address: "reserve".into(),
+                    amount: Uint128(u128::MAX / 2 + 1),
+                },
+            ],
+            ..init_msg()
+        };
+        let err = init(&mut deps, mock_env("creator", &[]), msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("total supply overflow"));
+    }
+
+    #[test]
+    fn handle_recurring_allowance() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let env = mock_env("sender", &[]);
+        let start = env.block.time;
+        let msg = HandleMsg::SetRecurringAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(1000),
+            period: 100,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let transfer_at = |deps: &mut Extern<_, _, _>, time: u64, amount: u128| {
+            let mut env = mock_env("third_party", &[]);
+            env.block.time = time;
+            let msg = HandleMsg::TransferFrom {
+                from: "sender".into(),
+                to: "recipient".into(),
+                amount: Uint128(amount),
+            };
+            handle(deps, env, msg)
+        };
+
+        // spending within the first period
+        let res = transfer_at(&mut deps, start + 10, 600).unwrap();
+        assert_eq!(res.log[5], log("remaining_allowance", 400));
+
+        // the period limit is exhausted until the period rolls over
+        let err = transfer_at(&mut deps, start + 99, 600).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientAllowance.into());
+        transfer_at(&mut deps, start + 99, 400).unwrap();
+        transfer_at(&mut deps, start + 99, 1).unwrap_err();
+
+        // the limit refills once the period has elapsed
+        let res = transfer_at(&mut deps, start + 100, 1000).unwrap();
+        assert_eq!(res.log[5], log("remaining_allowance", 0));
+        transfer_at(&mut deps, start + 150, 1).unwrap_err();
+
+        // skipped periods don't accumulate
+        let res = transfer_at(&mut deps, start + 450, 300).unwrap();
+        assert_eq!(res.log[5], log("remaining_allowance", 700));
+
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let third_party = deps.api.canonical_address(&"third_party".into()).unwrap();
+        let allowance = ReadOnlyAllowances::new(&sender, &deps.storage)
+            .get(&third_party)
+            .unwrap()
+            .unwrap();
+        assert_eq!(allowance.period_reset_at, Some(start + 500));
+    }
+
+    #[test]
+    fn query_config_after_init() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            cap: Some(Uint128(INITIAL_TOTAL_SUPPLY * 2)),
+            fee_bps: Some(25),
+            fee_collector: Some("collector".into()),
+            admin: Some("admin".into()),
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("creator", &[]), msg).unwrap();
+
+        let resp: ConfigResponse =
+            from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(
+            resp,
+            ConfigResponse {
+                name: "Craboken".into(),
+                symbol: "CRAB".into(),
+                decimals: 6,
+                total_supply: Uint128(INITIAL_TOTAL_SUPPLY),
+                cap: Some(Uint128(INITIAL_TOTAL_SUPPLY * 2)),
+                minters: vec!["minter".into()],
+                admin: "admin".into(),
+                paused: false,
+                fee_bps: 25,
+                fee_rounding: Rounding::Down,
+                fee_collector: "collector".into(),
+                max_mint_per_tx: None,
+                min_transfer_amount: None,
+                burnable_by_holders: true,
+                default_allowance_ttl: None,
+                daily_transfer_limit: None,
+                cooldown_blocks: None,
+                burn_fee: Uint128(0),
+            }
+        );
+    }
+
+    #[test]
+    fn handle_transfer_respects_vesting() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        let start = mock_env("minter", &[]).block.time;
+        let msg = HandleMsg::SetVesting {
+            address: "sender".into(),
+            locked: Uint128(INITIAL_BALANCE),
+            start,
+            cliff: 100,
+            duration: 1000,
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg.clone()).unwrap_err();
+        handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+
+        let transfer_at = |deps: &mut Extern<_, _, _>, time: u64, amount: u128| {
+            let mut env = mock_env("sender", &[]);
+            env.block.time = time;
+            let msg = HandleMsg::Transfer {
+                to: "recipient".into(),
+                amount: Uint128(amount),
+            };
+            handle(deps, env, msg)
+        };
+        let vested_at = |deps: &Extern<_, _, _>, time: u64| {
+            let mut block = mock_env("sender", &[]).block;
+            block.time = time;
+            let msg = QueryMsg::VestedAmount {
+                address: "sender".into(),
+                block,
+            };
+            let resp: VestedAmountResponse = from_binary(&query(deps, msg).unwrap()).unwrap();
+            resp
+        };
+
+        // before the cliff everything is locked
+        assert_eq!(
+            vested_at(&deps, start + 99),
+            VestedAmountResponse {
+                locked: Uint128(INITIAL_BALANCE),
+                vested: Uint128(0),
+            }
+        );
+        let err = transfer_at(&mut deps, start + 99, 1).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Transfer exceeds unlocked balance of 0")
+        );
+
+        // halfway through only the vested half can move
+        assert_eq!(
+            vested_at(&deps, start + 500).vested,
+            Uint128(INITIAL_BALANCE / 2)
+        );
+        transfer_at(&mut deps, start + 500, INITIAL_BALANCE / 2).unwrap();
+        transfer_at(&mut deps, start + 500, 1).unwrap_err();
+
+        // after the full duration the rest is unlocked
+        assert_eq!(
+            vested_at(&deps, start + 1000).vested,
+            Uint128(INITIAL_BALANCE)
+        );
+        transfer_at(&mut deps, start + 1000, INITIAL_BALANCE / 2).unwrap();
+
+        let recipient = deps.api.canonical_address(&"recipient".into()).unwrap();
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        assert_eq!(balances.get(&recipient).unwrap(), INITIAL_BALANCE);
+    }
+
+    #[test]
+    fn handle_sweep() {
+        let mut deps = mock_dependencies(16, &coins(500, "uscrt"));
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::Sweep {
+            denom: "uscrt".into(),
+            recipient: "rescuer".into(),
+        };
+        let err = handle(&mut deps, mock_env("sender", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, StdError::generic_err("only admin minter may sweep"));
+
+        let env = mock_env("minter", &[]);
+        let contract = env.contract.address.clone();
+        let resp = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(
+            resp.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: contract,
+                to_address: "rescuer".into(),
+                amount: coins(500, "uscrt"),
+            })]
+        );
+
+        let msg = HandleMsg::Sweep {
+            denom: "uatom".into(),
+            recipient: "rescuer".into(),
+        };
+        let err = handle(&mut deps, mock_env("minter", &[]), msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("No uatom to sweep"));
+    }
+
+    #[test]
+    fn mint_supply_overflow_writes_nothing() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+        State::write(&mut deps.storage)
+            .update(|mut state| {
+                state.total_supply = Uint128(u128::MAX - 10);
+                Ok(state)
+            })
+            .unwrap();
+
+        let env = mock_env("minter", &[]);
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(100),
         };
+        let err = handle(&mut deps, env, msg).unwrap_err();
+        assert_eq!(err, ContractError::Overflow.into());
 
-        let env = mock_env("creator", &[]);
+        // unit tests have no transactional rollback, so this proves the handler itself is atomic
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let balance = ReadOnlyBalances::new(&deps.storage).get(&sender).unwrap();
+        assert_eq!(balance, INITIAL_BALANCE);
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply, Uint128(u128::MAX - 10));
+    }
 
-        let _res = init(deps, env, msg).unwrap();
+    #[test]
+    fn burn_supply_underflow_writes_nothing() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        State::write(&mut deps.storage)
+            .update(|mut state| {
+                state.total_supply = Uint128(1);
+                Ok(state)
+            })
+            .unwrap();
+
+        let env = mock_env("treasury", &[]);
+        let msg = HandleMsg::Burn { amount: Uint128(2) };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let treasury = deps.api.canonical_address(&"treasury".into()).unwrap();
+        let balance = ReadOnlyBalances::new(&deps.storage).get(&treasury).unwrap();
+        assert_eq!(balance, INITIAL_TOTAL_SUPPLY);
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply, Uint128(1));
     }
 
-    fn mint<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
-        let msg = HandleMsg::Mint {
-            recipient: "sender".into(),
-            amount: Uint128(INITIAL_BALANCE),
+    #[test]
+    fn query_supply_share() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            total_supply: None,
+            initial_balances: vec![
+                InitBalance {
+                    address: "treasury".into(),
+                    amount: Uint128(750),
+                },
+                InitBalance {
+                    address: "holder".into(),
+                    amount: Uint128(250),
+                },
+            ],
+            ..init_msg()
         };
+        init(&mut deps, mock_env("creator", &[]), msg).unwrap();
 
-        let env = mock_env("minter", &[]);
+        let msg = QueryMsg::SupplyShare {
+            user: "holder".into(),
+        };
+        let share: u16 = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(share, 2500);
+    }
 
-        handle(deps, env, msg).unwrap();
+    #[test]
+    fn query_supply_share_zero_supply() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            total_supply: None,
+            initial_balances: vec![],
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("creator", &[]), msg).unwrap();
+
+        let msg = QueryMsg::SupplyShare {
+            user: "holder".into(),
+        };
+        let share: u16 = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(share, 0);
     }
 
-    fn set_allowance<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
+    #[test]
+    fn unlimited_allowance_is_not_decremented() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
         let msg = HandleMsg::SetAllowance {
             spender: "third_party".into(),
-            amount: Uint128(ALLOWANCE_AMOUNT),
+            amount: Uint128(u128::MAX),
             is_allowed: true,
+            expires: None,
         };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+
+        for _ in 0..3 {
+            let msg = HandleMsg::TransferFrom {
+                from: "sender".into(),
+                to: "recipient".into(),
+                amount: Uint128(1000),
+            };
+            let res = handle(&mut deps, mock_env("third_party", &[]), msg).unwrap();
+            assert_eq!(res.log[5], log("remaining_allowance", u128::MAX));
+        }
 
-        let env = mock_env("sender", &[]);
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let third_party = deps.api.canonical_address(&"third_party".into()).unwrap();
+        let allowance = ReadOnlyAllowances::new(&sender, &deps.storage)
+            .get(&third_party)
+            .unwrap()
+            .unwrap();
+        assert!(allowance.unlimited);
+        assert_eq!(allowance.amount, Uint128(u128::MAX));
 
-        handle(deps, env, msg).unwrap();
+        let recipient = deps.api.canonical_address(&"recipient".into()).unwrap();
+        let balances = ReadOnlyBalances::new(&deps.storage);
+        assert_eq!(balances.get(&recipient).unwrap(), 3000);
+
+        // decreasing turns it back into a finite allowance
+        let msg = HandleMsg::DecreaseAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(1),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        let allowance = ReadOnlyAllowances::new(&sender, &deps.storage)
+            .get(&third_party)
+            .unwrap()
+            .unwrap();
+        assert!(!allowance.unlimited);
+        assert_eq!(allowance.amount, Uint128(u128::MAX - 1));
     }
 
     #[test]
-    fn proper_init() {
+    fn handle_batch_set_allowance() {
         let mut deps = mock_dependencies(16, &[]);
+
         init_contract(&mut deps);
+        mint(&mut deps);
+
+        let spenders = ["alice", "bob", "carol"];
+        let msg = HandleMsg::BatchSetAllowance {
+            allowances: spenders
+                .iter()
+                .enumerate()
+                .map(|(i, spender)| AllowanceAction {
+                    spender: (*spender).into(),
+                    amount: Uint128(100 * (i as u128 + 1)),
+                    is_allowed: i != 2,
+                })
+                .collect(),
+        };
+        let res = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        assert_eq!(
+            res.log,
+            vec![
+                log("action", "batch_set_allowance"),
+                log("owner", "sender"),
+                log("spender", "alice"),
+                log("amount", 100),
+                log("is_allowed", true),
+                log("spender", "bob"),
+                log("amount", 200),
+                log("is_allowed", true),
+                log("spender", "carol"),
+                log("amount", 300),
+                log("is_allowed", false),
+            ]
+        );
+
+        for (i, spender) in spenders.iter().enumerate() {
+            let msg = QueryMsg::Allowance {
+                owner: "sender".into(),
+                spender: (*spender).into(),
+                block: None,
+            };
+            let resp: AllowanceResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+            assert_eq!(resp.amount, Uint128(100 * (i as u128 + 1)));
+            assert_eq!(resp.is_allowed, i != 2);
+        }
     }
 
     #[test]
-    fn handle_mint() {
+    fn handle_batch_set_allowance_rejects_invalid_entries() {
         let mut deps = mock_dependencies(16, &[]);
 
         init_contract(&mut deps);
-        mint(&mut deps);
 
-        let state = State::read(&deps.storage).load().unwrap();
-        assert_eq!(state.total_supply.u128(), TOTAL_SUPPLY);
+        let action = |spender: &str| AllowanceAction {
+            spender: spender.into(),
+            amount: Uint128(100),
+            is_allowed: true,
+        };
+
+        let msg = HandleMsg::BatchSetAllowance {
+            allowances: vec![action("alice"), action("sender")],
+        };
+        let err = handle(&mut deps, mock_env("sender", &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("cannot set allowance for yourself")
+        );
+
+        let msg = HandleMsg::BatchSetAllowance {
+            allowances: vec![action("alice"), action("bob"), action("alice")],
+        };
+        let err = handle(&mut deps, mock_env("sender", &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Duplicate spender in batch: alice")
+        );
+
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let alice = deps.api.canonical_address(&"alice".into()).unwrap();
+        let allowances = ReadOnlyAllowances::new(&sender, &deps.storage);
+        assert_eq!(allowances.get(&alice).unwrap(), None);
     }
 
     #[test]
-    fn handle_mint_unauthorized() {
+    fn init_with_initial_frozen() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            initial_frozen: vec!["mallory".into(), "trudy".into()],
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("creator", &[]), msg).unwrap();
+
+        for address in &["mallory", "trudy", "treasury"] {
+            let msg = QueryMsg::IsFrozen {
+                address: (*address).into(),
+            };
+            let resp: IsFrozenResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+            assert_eq!(resp.frozen, *address != "treasury");
+        }
+    }
+
+    #[test]
+    fn transfer_notifies_hooks() {
         let mut deps = mock_dependencies(16, &[]);
 
         init_contract(&mut deps);
+        mint(&mut deps);
 
-        let msg = HandleMsg::Mint {
-            recipient: "sender".into(),
+        for hook in &["analytics", "tax"] {
+            let msg = HandleMsg::AddHook {
+                contract: (*hook).into(),
+                code_hash: format!("{}_hash", hook),
+            };
+            handle(&mut deps, mock_env("sender", &[]), msg.clone()).unwrap_err();
+            handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+        }
+
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
             amount: Uint128(1000),
         };
+        let resp = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
 
-        let env = mock_env("not_minter", &[]);
+        let hook_msg = to_binary(&TransferHookMsg::TransferHook {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(1000),
+            fee: Uint128(0),
+            burn_fee: Uint128(0),
+        })
+        .unwrap();
+        assert_eq!(
+            resp.messages,
+            vec![
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "analytics".into(),
+                    callback_code_hash: "analytics_hash".into(),
+                    msg: hook_msg.clone(),
+                    send: vec![],
+                }),
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "tax".into(),
+                    callback_code_hash: "tax_hash".into(),
+                    msg: hook_msg,
+                    send: vec![],
+                }),
+            ]
+        );
+
+        let msg = HandleMsg::RemoveHook {
+            contract: "analytics".into(),
+        };
+        handle(&mut deps, mock_env("minter", &[]), msg.clone()).unwrap();
+        let err = handle(&mut deps, mock_env("minter", &[]), msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Hook is not registered"));
 
-        let err = handle(&mut deps, env, msg).unwrap_err();
-        assert_eq!(err, StdError::unauthorized());
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(1000),
+        };
+        let resp = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        assert_eq!(resp.messages.len(), 1);
+
+        // admin clawbacks are reported to hooks as well
+        set_frozen(&mut deps, "sender", true);
+        let msg = HandleMsg::ForceTransfer {
+            from: "sender".into(),
+            to: "treasury".into(),
+            amount: Uint128(1000),
+        };
+        let resp = handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+        let hook_msg = to_binary(&TransferHookMsg::TransferHook {
+            from: "sender".into(),
+            to: "treasury".into(),
+            amount: Uint128(1000),
+            fee: Uint128(0),
+            burn_fee: Uint128(0),
+        })
+        .unwrap();
+        assert_eq!(
+            resp.messages,
+            vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "tax".into(),
+                callback_code_hash: "tax_hash".into(),
+                msg: hook_msg,
+                send: vec![],
+            })]
+        );
     }
 
     #[test]
-    fn handle_mint_too_many() {
+    fn hook_registry_is_capped() {
         let mut deps = mock_dependencies(16, &[]);
 
         init_contract(&mut deps);
 
-        let msg = HandleMsg::Mint {
-            recipient: "sender".into(),
-            amount: Uint128(u128::MAX),
+        for i in 0..MAX_TRANSFER_HOOKS {
+            let msg = HandleMsg::AddHook {
+                contract: format!("hook{}", i).into(),
+                code_hash: "hash".into(),
+            };
+            handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+        }
+
+        let msg = HandleMsg::AddHook {
+            contract: "hook0".into(),
+            code_hash: "hash".into(),
         };
+        let err = handle(&mut deps, mock_env("minter", &[]), msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Hook is already registered"));
 
-        let env = mock_env("minter", &[]);
+        let msg = HandleMsg::AddHook {
+            contract: "one_too_many".into(),
+            code_hash: "hash".into(),
+        };
+        let err = handle(&mut deps, mock_env("minter", &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Cannot register more than 10 hooks")
+        );
+    }
 
-        handle(&mut deps, env, msg).unwrap_err();
+    #[test]
+    fn query_allowances_by_spender() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        for (owner, amount) in &[("alice", 100u128), ("bob", 200)] {
+            let msg = HandleMsg::SetAllowance {
+                spender: "dapp".into(),
+                amount: Uint128(*amount),
+                is_allowed: true,
+                expires: None,
+            };
+            handle(&mut deps, mock_env(*owner, &[]), msg).unwrap();
+        }
+        let msg = HandleMsg::SetAllowance {
+            spender: "other".into(),
+            amount: Uint128(300),
+            is_allowed: true,
+            expires: None,
+        };
+        handle(&mut deps, mock_env("carol", &[]), msg).unwrap();
+
+        let query_spender = |deps: &Extern<_, _, _>| {
+            let msg = QueryMsg::AllowancesBySpender {
+                spender: "dapp".into(),
+                start_after: None,
+                limit: None,
+            };
+            let resp: AllowancesBySpenderResponse =
+                from_binary(&query(deps, msg).unwrap()).unwrap();
+            let mut allowances = resp.allowances;
+            allowances.sort_by(|a, b| a.owner.as_str().cmp(b.owner.as_str()));
+            allowances
+        };
+
+        assert_eq!(
+            query_spender(&deps),
+            vec![
+                SpenderAllowanceInfo {
+                    owner: "alice".into(),
+                    amount: Uint128(100),
+                    is_allowed: true,
+                    expires: Expiration::Never,
+                },
+                SpenderAllowanceInfo {
+                    owner: "bob".into(),
+                    amount: Uint128(200),
+                    is_allowed: true,
+                    expires: Expiration::Never,
+                },
+            ]
+        );
+
+        // removing the allowance drops it from the index too
+        let msg = HandleMsg::RevokeAllowance {
+            spender: "dapp".into(),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let allowances = query_spender(&deps);
+        assert_eq!(allowances.len(), 1);
+        assert_eq!(allowances[0].owner, HumanAddr::from("bob"));
     }
 
     #[test]
-    fn handle_transfer() {
+    fn query_token_info_total_supply_display() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        let msg = InitMsg {
+            decimals: 2,
+            total_supply: None,
+            initial_balances: vec![InitBalance {
+                address: "holder".into(),
+                amount: Uint128(12345),
+            }],
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("creator", &[]), msg).unwrap();
+
+        let resp: TokenInfoResponse =
+            from_binary(&query(&deps, QueryMsg::TokenInfo {}).unwrap()).unwrap();
+        assert_eq!(resp.total_supply, Uint128(12345));
+        assert_eq!(resp.total_supply_display, "123.45");
+    }
+
+    #[test]
+    fn handle_daily_transfer_limit() {
         let mut deps = mock_dependencies(16, &[]);
 
         init_contract(&mut deps);
         mint(&mut deps);
+        set_allowance(&mut deps);
 
-        let sender_env = mock_env("sender", &[]);
+        let msg = HandleMsg::UpdateDailyLimit {
+            daily_transfer_limit: Some(Uint128(1000)),
+        };
+        let err = handle(&mut deps, mock_env("sender", &[]), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("only admin minter may update the daily limit")
+        );
+        handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+
+        let start = mock_env("sender", &[]).block.time;
+        let transfer_at = |deps: &mut Extern<_, _, _>, time: u64, amount: u128| {
+            let mut env = mock_env("sender", &[]);
+            env.block.time = time;
+            let msg = HandleMsg::Transfer {
+                to: "recipient".into(),
+                amount: Uint128(amount),
+            };
+            handle(deps, env, msg)
+        };
+
+        // transfers within the limit
+        transfer_at(&mut deps, start, 600).unwrap();
+
+        // transfer_from draws on the owner's limit too
+        let mut env = mock_env("third_party", &[]);
+        env.block.time = start + 10;
+        let msg = HandleMsg::TransferFrom {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(300),
+        };
+        handle(&mut deps, env, msg.clone()).unwrap();
+
+        // hitting the limit
+        let err = transfer_at(&mut deps, start + 20, 101).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Transfer exceeds daily limit, 100 remaining")
+        );
+        let mut env = mock_env("third_party", &[]);
+        env.block.time = start + 20;
+        handle(&mut deps, env, msg).unwrap_err();
+        transfer_at(&mut deps, start + 20, 100).unwrap();
+        transfer_at(&mut deps, start + DAY_SECONDS - 1, 1).unwrap_err();
 
+        // the window resets once a day has elapsed
+        transfer_at(&mut deps, start + DAY_SECONDS, 1000).unwrap();
+        transfer_at(&mut deps, start + DAY_SECONDS, 1).unwrap_err();
+
+        // other addresses have their own window
+        let env = mock_env("treasury", &[]);
         let msg = HandleMsg::Transfer {
             to: "recipient".into(),
             amount: Uint128(1000),
         };
+        handle(&mut deps, env, msg).unwrap();
 
-        handle(&mut deps, sender_env, msg).unwrap();
+        // removing the limit lifts the cap
+        let msg = HandleMsg::UpdateDailyLimit {
+            daily_transfer_limit: None,
+        };
+        handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+        transfer_at(&mut deps, start + DAY_SECONDS, 5000).unwrap();
+    }
 
-        let sender = deps
-            .api
-            .canonical_address(&HumanAddr::from("sender"))
-            .unwrap();
-        let recipient = deps
+    #[test]
+    fn query_is_minter() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let is_minter = |deps: &Extern<_, _, _>, address: &str| -> bool {
+            let msg = QueryMsg::IsMinter {
+                address: address.into(),
+            };
+            from_binary(&query(deps, msg).unwrap()).unwrap()
+        };
+
+        assert!(is_minter(&deps, "minter"));
+        assert!(!is_minter(&deps, "random"));
+    }
+
+    #[test]
+    fn handle_set_allowance_log() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+
+        let msg = HandleMsg::SetAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
+            is_allowed: false,
+            expires: None,
+        };
+        let res = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        assert_eq!(
+            res.log,
+            vec![
+                log(ACTION, "set_allowance"),
+                log(OWNER, "sender"),
+                log(SPENDER, "third_party"),
+                log(AMOUNT, ALLOWANCE_AMOUNT),
+                log("is_allowed", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn migrate_from_baseline() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        seed_baseline(&mut deps);
+        let res = migrate(&mut deps, mock_env("creator", &[]), baseline_migrate_msg()).unwrap();
+        assert_eq!(
+            res.log,
+            vec![
+                log("pruned_balances", 1),
+                log("allowances", 1),
+                log("holder_count", 1),
+            ]
+        );
+
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.name, "Craboken");
+        assert_eq!(state.total_supply, Uint128(INITIAL_BALANCE));
+        assert_eq!(state.admin, HumanAddr::from("minter"));
+        assert_eq!(state.fee_collector, HumanAddr::from("minter"));
+        assert!(state.burnable_by_holders);
+        let contract = deps
             .api
-            .canonical_address(&HumanAddr::from("recipient"))
-            .unwrap();
+            .canonical_address(&mock_env("creator", &[]).contract.address);
+        assert_eq!(
+            state.burn_address,
+            CanonicalAddr::from(vec![0; contract.unwrap().len()])
+        );
+        assert_supply_invariant(&deps);
 
-        let balances = ReadOnlyBalances::new(&deps.storage);
+        let msg = QueryMsg::GetBalance {
+            user: "sender".into(),
+        };
+        let resp: BalanceResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp.amount, Uint128(INITIAL_BALANCE));
+        let drained = deps.api.canonical_address(&"drained".into()).unwrap();
+        assert!(!ReadOnlyBalances::new(&deps.storage).exists(&drained));
+
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let spender = deps.api.canonical_address(&"third_party".into()).unwrap();
+        let owners = ReadOnlySpenderIndex::new(&spender, &deps.storage).owners(None, 10);
+        assert_eq!(owners, vec![sender]);
+        let msg = QueryMsg::Allowance {
+            owner: "sender".into(),
+            spender: "third_party".into(),
+            block: None,
+        };
+        let resp: AllowanceResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp.amount, Uint128(ALLOWANCE_AMOUNT));
+        assert!(resp.is_allowed);
 
-        let sender_balance = balances.get(&sender).unwrap();
-        assert_eq!(sender_balance, INITIAL_BALANCE - 1000);
+        // the old allowance is spendable and the old minter can still mint
+        let msg = HandleMsg::TransferFrom {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
+        };
+        handle(&mut deps, mock_env("third_party", &[]), msg).unwrap();
+        let msg = HandleMsg::Mint {
+            recipient: "recipient".into(),
+            amount: Uint128(1),
+        };
+        handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+        assert_supply_invariant(&deps);
+    }
 
-        let recipient_balance = balances.get(&recipient).unwrap();
-        assert_eq!(recipient_balance, 1000);
+    #[test]
+    fn migrate_from_baseline_requires_token_info() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        seed_baseline(&mut deps);
+        let env = mock_env("creator", &[]);
+        let err = migrate(&mut deps, env, MigrateMsg::default()).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Migrating from the original release requires token_info")
+        );
+
+        let msg = MigrateMsg {
+            token_info: Some(TokenMetadata {
+                symbol: "C".into(),
+                ..baseline_migrate_msg().token_info.unwrap()
+            }),
+        };
+        let err = migrate(&mut deps, mock_env("creator", &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Ticker symbol must be 3-12 alphanumeric characters")
+        );
+        assert_eq!(
+            ContractVersion::read(&deps.storage).may_load().unwrap(),
+            None
+        );
     }
 
     #[test]
-    fn handle_burn() {
+    fn handle_send_to_burn_address() {
         let mut deps = mock_dependencies(16, &[]);
 
         init_contract(&mut deps);
         mint(&mut deps);
 
-        let sender_env = mock_env("sender", &[]);
+        let supply = |deps: &Extern<_, _, _>| -> Uint128 {
+            let resp = query(deps, QueryMsg::TokenInfo {}).unwrap();
+            let resp: TokenInfoResponse = from_binary(&resp).unwrap();
+            resp.total_supply
+        };
+        let burned = |deps: &Extern<_, _, _>| -> Uint128 {
+            let resp = query(deps, QueryMsg::BurnedBalance {}).unwrap();
+            let resp: BalanceResponse = from_binary(&resp).unwrap();
+            resp.amount
+        };
+        let initial_supply = supply(&deps);
 
+        let msg = HandleMsg::SendToBurnAddress {
+            amount: Uint128(1000),
+        };
+        let res = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        assert_eq!(
+            res.log,
+            vec![
+                log(ACTION, "send_to_burn_address"),
+                log(FROM, "sender"),
+                log(AMOUNT, 1000),
+            ]
+        );
+
+        // the tokens are parked, not destroyed
+        assert_eq!(supply(&deps), initial_supply);
+        assert_eq!(burned(&deps), Uint128(1000));
+        let sender = deps.api.canonical_address(&"sender".into()).unwrap();
+        let balance = ReadOnlyBalances::new(&deps.storage).get(&sender).unwrap();
+        assert_eq!(balance, INITIAL_BALANCE - 1000);
+        assert_supply_invariant(&deps);
+
+        // a real burn reduces supply and doesn't touch the burn address
         let msg = HandleMsg::Burn {
+            amount: Uint128(500),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        assert_eq!(supply(&deps), (initial_supply.u128() - 500).into());
+        assert_eq!(burned(&deps), Uint128(1000));
+        assert_supply_invariant(&deps);
+
+        let msg = HandleMsg::SendToBurnAddress {
+            amount: Uint128(INITIAL_BALANCE),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap_err();
+    }
+
+    #[test]
+    fn handle_transfer_ensure_balance() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        // the recipient ends exactly at the threshold
+        let msg = HandleMsg::TransferEnsureBalance {
+            to: "recipient".into(),
             amount: Uint128(1000),
+            min_resulting_balance: Uint128(1000),
         };
+        let res = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        assert_eq!(res.log[0], log(ACTION, "transfer"));
+        assert_eq!(res.log[5], log("to_balance", 1000));
 
-        handle(&mut deps, sender_env, msg).unwrap();
+        // the recipient would end below the threshold
+        let msg = HandleMsg::TransferEnsureBalance {
+            to: "recipient".into(),
+            amount: Uint128(1000),
+            min_resulting_balance: Uint128(2001),
+        };
+        let err = handle(&mut deps, mock_env("sender", &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Recipient balance 2000 is below the required 2001")
+        );
+    }
 
-        let balances = ReadOnlyBalances::new(&deps.storage);
+    #[test]
+    fn unauthorized_errors_name_the_expected_sender() {
+        let mut deps = mock_dependencies(16, &[]);
 
-        let sender = deps
-            .api
-            .canonical_address(&HumanAddr::from("sender"))
-            .unwrap();
-        let sender_balance = balances.get(&sender).unwrap();
-        assert_eq!(sender_balance, INITIAL_BALANCE - 1000);
+        init_contract(&mut deps);
+        let msg = HandleMsg::AddMinter {
+            minter: "second_minter".into(),
+        };
+        handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+
+        let msg = HandleMsg::Mint {
+            recipient: "sender".into(),
+            amount: Uint128(1),
+        };
+        let err = handle(&mut deps, mock_env("sender", &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("only minter minter, second_minter may mint")
+        );
+
+        let msg = HandleMsg::SetPaused { paused: true };
+        let err = handle(&mut deps, mock_env("sender", &[]), msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("only admin minter may pause"));
+    }
+
+    #[test]
+    fn handle_distribute_pro_rata() {
+        let mut deps = mock_dependencies(16, &[]);
+
+        init_contract(&mut deps);
+        mint(&mut deps);
+
+        // park some tokens at the burn address, which is left out of the distribution
+        let msg = HandleMsg::SendToBurnAddress {
+            amount: Uint128(10_000),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+
+        let msg = HandleMsg::DistributeProRata {
+            total: Uint128(10_100),
+        };
+        let err = handle(&mut deps, mock_env("sender", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, StdError::generic_err("only minter minter may mint"));
+
+        let res = handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+        assert_eq!(res.log[2], log("holders", 2));
+        let balance = |user: &str| {
+            let user = deps.api.canonical_address(&user.into()).unwrap();
+            ReadOnlyBalances::new(&deps.storage).get(&user).unwrap()
+        };
+        // 100_000_000 : 990_000 splits 10_100 into 10_000.99 and 99.01, and the
+        // largest holder picks up the rounding remainder
+        let treasury_share = balance("treasury") - INITIAL_TOTAL_SUPPLY;
+        let sender_share = balance("sender") - (INITIAL_BALANCE - 10_000);
+        assert_eq!(treasury_share, 10_001);
+        assert_eq!(sender_share, 99);
+        assert_eq!(treasury_share + sender_share, 10_100);
+
+        let resp = query(&deps, QueryMsg::BurnedBalance {}).unwrap();
+        let resp: BalanceResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.amount, Uint128(10_000));
 
         let state = State::read(&deps.storage).load().unwrap();
-        assert_eq!(state.total_supply.u128(), TOTAL_SUPPLY - 1000);
+        assert_eq!(
+            state.total_supply.u128(),
+            INITIAL_TOTAL_SUPPLY + INITIAL_BALANCE + 10_100
+        );
+        assert_supply_invariant(&deps);
     }
 
     #[test]
-    fn handle_burn_more_than_total_supply() {
+    fn query_top_holders() {
         let mut deps = mock_dependencies(16, &[]);
 
         init_contract(&mut deps);
-        mint(&mut deps);
 
-        State::write(&mut deps.storage)
-            .update(|mut state| {
-                state.total_supply = Uint128(0);
-                Ok(state)
-            })
-            .unwrap();
-
-        let sender_env = mock_env("sender", &[]);
+        for (user, amount) in &[
+            ("carol", 3000),
+            ("alice", 1000),
+            ("erin", 5000),
+            ("bob", 2000),
+            ("dave", 4000),
+        ] {
+            let msg = HandleMsg::Mint {
+                recipient: (*user).into(),
+                amount: Uint128(*amount),
+            };
+            handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+        }
 
-        let msg = HandleMsg::Burn {
-            amount: Uint128(1000),
+        let top_holders = |limit: u32| -> Vec<(HumanAddr, u128)> {
+            let resp = query(&deps, QueryMsg::TopHolders { limit }).unwrap();
+            let resp: TopHoldersResponse = from_binary(&resp).unwrap();
+            resp.holders
+                .into_iter()
+                .map(|holder| (holder.address, holder.amount.u128()))
+                .collect()
         };
 
-        handle(&mut deps, sender_env, msg).unwrap_err();
+        assert_eq!(
+            top_holders(3),
+            vec![
+                ("treasury".into(), INITIAL_TOTAL_SUPPLY),
+                ("erin".into(), 5000),
+                ("dave".into(), 4000),
+            ]
+        );
+        assert_eq!(top_holders(0), vec![]);
+        assert_eq!(top_holders(u32::MAX).len(), 6);
     }
 
     #[test]
-    fn handle_set_allowance() {
+    fn handle_transfer_cooldown() {
         let mut deps = mock_dependencies(16, &[]);
 
         init_contract(&mut deps);
-        mint(&mut deps);
-        set_allowance(&mut deps);
 
-        let owner = deps
-            .api
-            .canonical_address(&HumanAddr::from("sender"))
-            .unwrap();
-        let third_party = deps
-            .api
-            .canonical_address(&HumanAddr::from("third_party"))
-            .unwrap();
+        let msg = HandleMsg::UpdateCooldown {
+            cooldown_blocks: Some(10),
+        };
+        let err = handle(&mut deps, mock_env("sender", &[]), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("only admin minter may update the cooldown")
+        );
+        handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+
+        let start = mock_env("treasury", &[]).block.height;
+        let transfer_at = |deps: &mut Extern<_, _, _>, height: u64, from: &str, to: &str| {
+            let mut env = mock_env(from, &[]);
+            env.block.height = height;
+            let msg = HandleMsg::Transfer {
+                to: to.into(),
+                amount: Uint128(100),
+            };
+            handle(deps, env, msg)
+        };
 
-        let allowances = ReadOnlyAllowances::new(&owner, &deps.storage);
-        let allowance = allowances.get(&third_party).unwrap().unwrap();
-        assert!(allowance.is_allowed);
-        assert_eq!(allowance.amount.u128(), ALLOWANCE_AMOUNT);
+        // the treasury has never received, so it can send right away
+        transfer_at(&mut deps, start, "treasury", "alice").unwrap();
+
+        // alice is blocked for the cooldown window after receiving
+        let err = transfer_at(&mut deps, start + 9, "alice", "bob").unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(format!(
+                "Sender is in a transfer cooldown until height {}",
+                start + 10
+            ))
+        );
+
+        // and free to send once it has passed
+        transfer_at(&mut deps, start + 10, "alice", "bob").unwrap();
+
+        // turning the cooldown off lifts the restriction
+        let msg = HandleMsg::UpdateCooldown {
+            cooldown_blocks: None,
+        };
+        handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+        transfer_at(&mut deps, start + 10, "bob", "alice").unwrap();
     }
 
     #[test]
-    fn handle_transfer_from() {
+    fn spend_paths_share_the_same_arithmetic() {
         let mut deps = mock_dependencies(16, &[]);
 
         init_contract(&mut deps);
         mint(&mut deps);
-        set_allowance(&mut deps);
+        assert_eq!(holder_count(&deps.storage).unwrap(), 2);
 
-        let third_party_env = mock_env("third_party", &[]);
+        // every path that takes from a balance reports a shortfall the same way
+        let expected: StdError = ContractError::InsufficientFunds {
+            balance: INITIAL_BALANCE,
+            required: INITIAL_BALANCE + 1,
+        }
+        .into();
+        let overspends = vec![
+            HandleMsg::Transfer {
+                to: "recipient".into(),
+                amount: Uint128(INITIAL_BALANCE + 1),
+            },
+            HandleMsg::Burn {
+                amount: Uint128(INITIAL_BALANCE + 1),
+            },
+        ];
+        for msg in overspends {
+            let err = handle(&mut deps, mock_env("sender", &[]), msg).unwrap_err();
+            assert_eq!(err, expected);
+        }
 
-        let msg = HandleMsg::TransferFrom {
-            from: "sender".into(),
+        // draining an account through either path drops it from the holders
+        let msg = HandleMsg::Transfer {
             to: "recipient".into(),
-            amount: Uint128(1000),
+            amount: Uint128(INITIAL_BALANCE),
         };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        assert_eq!(holder_count(&deps.storage).unwrap(), 2);
 
-        handle(&mut deps, third_party_env, msg).unwrap();
-
-        let sender = deps
-            .api
-            .canonical_address(&HumanAddr::from("sender"))
-            .unwrap();
-        let recipient = deps
-            .api
-            .canonical_address(&HumanAddr::from("recipient"))
-            .unwrap();
-        let third_party = deps
-            .api
-            .canonical_address(&HumanAddr::from("third_party"))
-            .unwrap();
-
-        let balances = ReadOnlyBalances::new(&deps.storage);
-        let recipient_balance = balances.get(&recipient).unwrap();
-        assert_eq!(recipient_balance, 1000);
+        let msg = HandleMsg::Burn {
+            amount: Uint128(INITIAL_BALANCE),
+        };
+        handle(&mut deps, mock_env("recipient", &[]), msg).unwrap();
+        assert_eq!(holder_count(&deps.storage).unwrap(), 1);
 
-        let allowances = ReadOnlyAllowances::new(&sender, &deps.storage);
-        let allowance = allowances.get(&third_party).unwrap().unwrap();
-        assert_eq!(allowance.amount.u128(), ALLOWANCE_AMOUNT - 1000);
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply.u128(), INITIAL_TOTAL_SUPPLY);
+        assert_supply_invariant(&deps);
     }
 
     #[test]
-    fn handle_transfer_from_too_many() {
+    fn query_total_allowance_granted() {
         let mut deps = mock_dependencies(16, &[]);
 
         init_contract(&mut deps);
         mint(&mut deps);
-        set_allowance(&mut deps);
 
-        let third_party_env = mock_env("third_party", &[]);
+        let env = mock_env("sender", &[]);
+        let block = env.block.clone();
+        let allowances = vec![
+            ("spender1", 1_000, Expiration::Never),
+            ("spender2", 2_000, Expiration::AtHeight(block.height + 10)),
+            ("spender3", 4_000, Expiration::AtHeight(block.height + 5)),
+        ];
+        for (spender, amount, expires) in allowances {
+            let msg = HandleMsg::SetAllowance {
+                spender: spender.into(),
+                amount: Uint128(amount),
+                is_allowed: true,
+                expires: Some(expires),
+            };
+            handle(&mut deps, env.clone(), msg).unwrap();
+        }
 
-        let msg = HandleMsg::TransferFrom {
-            from: "sender".into(),
-            to: "recipient".into(),
-            amount: Uint128(ALLOWANCE_AMOUNT * 2),
+        let total = |deps: &Extern<_, _, _>, height: u64| -> Uint128 {
+            let msg = QueryMsg::TotalAllowanceGranted {
+                owner: "sender".into(),
+                block: BlockInfo {
+                    height,
+                    ..block.clone()
+                },
+            };
+            from_binary(&query(deps, msg).unwrap()).unwrap()
         };
 
-        handle(&mut deps, third_party_env, msg).unwrap_err();
+        assert_eq!(total(&deps, block.height), Uint128(7_000));
+        // spender3 has expired, the remaining two are summed
+        assert_eq!(total(&deps, block.height + 5), Uint128(3_000));
+        assert_eq!(total(&deps, block.height + 10), Uint128(1_000));
+
+        let msg = QueryMsg::TotalAllowanceGranted {
+            owner: "recipient".into(),
+            block: block.clone(),
+        };
+        let resp: Uint128 = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(resp, Uint128(0));
+
+        // an unlimited allowance saturates the total instead of overflowing it
+        let msg = HandleMsg::SetAllowance {
+            spender: "spender4".into(),
+            amount: Uint128(u128::MAX),
+            is_allowed: true,
+            expires: Some(Expiration::AtHeight(block.height + 5)),
+        };
+        handle(&mut deps, env, msg).unwrap();
+        assert_eq!(total(&deps, block.height), Uint128(u128::MAX));
+        assert_eq!(total(&deps, block.height + 5), Uint128(3_000));
     }
 
     #[test]
-    fn handle_transfer_from_unauthorized() {
+    fn handle_transfer_burns_fee_from_sender() {
         let mut deps = mock_dependencies(16, &[]);
 
-        init_contract(&mut deps);
+        let msg = InitMsg {
+            burn_fee: Uint128(100),
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("creator", &[]), msg).unwrap();
         mint(&mut deps);
 
-        let third_party_env = mock_env("third_party", &[]);
+        let balance = |deps: &Extern<_, _, _>, user: &str| -> u128 {
+            let msg = QueryMsg::GetBalance { user: user.into() };
+            let resp: BalanceResponse = from_binary(&query(deps, msg).unwrap()).unwrap();
+            resp.amount.u128()
+        };
 
-        let msg = HandleMsg::TransferFrom {
-            from: "sender".into(),
+        let msg = HandleMsg::Transfer {
             to: "recipient".into(),
-            amount: Uint128(1000),
+            amount: Uint128(1_000),
         };
+        let res = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        assert!(res.log.contains(&log("burn_fee", "100")));
+        assert!(res
+            .log
+            .contains(&log("from_balance", INITIAL_BALANCE - 1_100)));
+
+        assert_eq!(balance(&deps, "sender"), INITIAL_BALANCE - 1_100);
+        assert_eq!(balance(&deps, "recipient"), 1_000);
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply.u128(), TOTAL_SUPPLY - 100);
+        assert_supply_invariant(&deps);
 
-        let err = handle(&mut deps, third_party_env, msg).unwrap_err();
-        assert_eq!(err, StdError::unauthorized());
+        // the amount alone fits, but not together with the burn fee
+        let remaining = INITIAL_BALANCE - 1_100;
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(remaining),
+        };
+        let err = handle(&mut deps, mock_env("sender", &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientFunds {
+                balance: remaining,
+                required: remaining + 100,
+            }
+            .into()
+        );
+        assert_eq!(balance(&deps, "sender"), remaining);
+        assert_eq!(balance(&deps, "recipient"), 1_000);
     }
 
     #[test]
-    fn handle_transfer_from_allowance_is_false() {
-        let mut deps = mock_dependencies(16, &[]);
+    fn every_transfer_entry_point_charges_the_fee() {
+        let cases = vec![
+            (
+                "sender",
+                HandleMsg::BatchTransfer {
+                    transfers: vec![TransferAction {
+                        to: "recipient".into(),
+                        amount: Uint128(10_000),
+                    }],
+                },
+                10_000,
+                100,
+            ),
+            (
+                "sender",
+                HandleMsg::TransferAll {
+                    to: "recipient".into(),
+                },
+                INITIAL_BALANCE,
+                INITIAL_BALANCE / 100,
+            ),
+            (
+                "sender",
+                HandleMsg::Send {
+                    contract: "recipient".into(),
+                    code_hash: "hash".into(),
+                    amount: Uint128(10_000),
+                    msg: Binary::from(b"payload".to_vec()),
+                },
+                10_000,
+                100,
+            ),
+            (
+                "third_party",
+                HandleMsg::TransferFrom {
+                    from: "sender".into(),
+                    to: "recipient".into(),
+                    amount: Uint128(10_000),
+                },
+                10_000,
+                100,
+            ),
+            (
+                "third_party",
+                HandleMsg::TransferFromPartial {
+                    from: "sender".into(),
+                    to: "recipient".into(),
+                    amount: Uint128(10_000),
+                },
+                10_000,
+                100,
+            ),
+        ];
+
+        for (signer, msg, sent, fee) in cases {
+            let mut deps = mock_dependencies(16, &[]);
+            let init_msg = InitMsg {
+                fee_bps: Some(100),
+                fee_collector: Some("collector".into()),
+                ..init_msg()
+            };
+            init(&mut deps, mock_env("creator", &[]), init_msg).unwrap();
+            mint(&mut deps);
+            set_allowance(&mut deps);
+
+            let res = handle(&mut deps, mock_env(signer, &[]), msg).unwrap();
+            assert_eq!(res.log.last(), Some(&log("fee", fee)));
+
+            let balances = ReadOnlyBalances::new(&deps.storage);
+            for (user, amount) in &[
+                ("sender", INITIAL_BALANCE - sent),
+                ("recipient", sent - fee),
+                ("collector", fee),
+            ] {
+                let user = deps.api.canonical_address(&(*user).into()).unwrap();
+                assert_eq!(balances.get(&user).unwrap(), *amount);
+            }
+        }
 
-        init_contract(&mut deps);
+        // the receiver is told the amount that arrived
+        let mut deps = mock_dependencies(16, &[]);
+        let init_msg = InitMsg {
+            fee_bps: Some(100),
+            fee_collector: Some("collector".into()),
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("creator", &[]), init_msg).unwrap();
         mint(&mut deps);
-
-        // set allowance
-        let msg = HandleMsg::SetAllowance {
-            spender: "third_party".into(),
-            amount: Uint128(ALLOWANCE_AMOUNT),
-            is_allowed: false,
+        let msg = HandleMsg::Send {
+            contract: "receiver".into(),
+            code_hash: "hash".into(),
+            amount: Uint128(10_000),
+            msg: Binary::from(b"payload".to_vec()),
         };
+        let res = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        let receive_msg = ReceiverHandleMsg::Receive {
+            sender: "sender".into(),
+            amount: Uint128(9_900),
+            msg: Binary::from(b"payload".to_vec()),
+        };
+        assert_eq!(
+            res.messages.last(),
+            Some(&CosmosMsg::from(WasmMsg::Execute {
+                contract_addr: "receiver".into(),
+                callback_code_hash: "hash".into(),
+                msg: to_binary(&receive_msg).unwrap(),
+                send: vec![],
+            }))
+        );
+    }
 
-        let env = mock_env("sender", &[]);
+    #[test]
+    fn min_transfer_amount_applies_to_every_send_path() {
+        let mut deps = mock_dependencies(16, &[]);
 
-        handle(&mut deps, env, msg).unwrap();
+        let msg = InitMsg {
+            min_transfer_amount: Some(Uint128(10)),
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("creator", &[]), msg).unwrap();
+        mint(&mut deps);
 
-        // transfer from
-        let third_party_env = mock_env("third_party", &[]);
+        let msg = HandleMsg::Transfer {
+            to: "dust".into(),
+            amount: Uint128(10),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
 
-        let msg = HandleMsg::TransferFrom {
-            from: "sender".into(),
+        // leave "dust" with a balance below the minimum
+        let msg = HandleMsg::Burn { amount: Uint128(1) };
+        handle(&mut deps, mock_env("dust", &[]), msg).unwrap();
+        let msg = HandleMsg::TransferAll {
             to: "recipient".into(),
-            amount: Uint128(1000),
         };
-
-        let err = handle(&mut deps, third_party_env, msg).unwrap_err();
-        assert_eq!(err, StdError::unauthorized());
+        let err = handle(&mut deps, mock_env("dust", &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Transfer amount must be at least 10")
+        );
+
+        let cases = vec![
+            (
+                "sender",
+                HandleMsg::BatchTransfer {
+                    transfers: vec![
+                        TransferAction {
+                            to: "recipient".into(),
+                            amount: Uint128(10),
+                        },
+                        TransferAction {
+                            to: "recipient".into(),
+                            amount: Uint128(1),
+                        },
+                    ],
+                },
+            ),
+            (
+                "sender",
+                HandleMsg::Send {
+                    contract: "receiver".into(),
+                    code_hash: "hash".into(),
+                    amount: Uint128(9),
+                    msg: Binary::from(b"payload".to_vec()),
+                },
+            ),
+        ];
+        for (signer, msg) in cases {
+            let err = handle(&mut deps, mock_env(signer, &[]), msg).unwrap_err();
+            assert_eq!(
+                err,
+                StdError::generic_err("Transfer amount must be at least 10")
+            );
+        }
     }
 
     #[test]
-    fn handle_burn_from() {
+    fn transfer_from_partial_uses_spendable_amounts() {
+        let partial_at =
+            |deps: &mut Extern<_, _, _>, time: u64, amount: u128| -> StdResult<HandleResponse> {
+                let mut env = mock_env("third_party", &[]);
+                env.block.time = time;
+                let msg = HandleMsg::TransferFromPartial {
+                    from: "sender".into(),
+                    to: "recipient".into(),
+                    amount: Uint128(amount),
+                };
+                handle(deps, env, msg)
+            };
+
+        // a spent recurring allowance refills once its period rolls over
         let mut deps = mock_dependencies(16, &[]);
-
         init_contract(&mut deps);
         mint(&mut deps);
-        set_allowance(&mut deps);
-
-        let third_party_env = mock_env("third_party", &[]);
-
-        let msg = HandleMsg::BurnFrom {
-            from: "sender".into(),
+        let start = mock_env("sender", &[]).block.time;
+        let msg = HandleMsg::SetRecurringAllowance {
+            spender: "third_party".into(),
             amount: Uint128(1000),
+            period: 100,
         };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        partial_at(&mut deps, start + 10, 1000).unwrap();
+        let res = partial_at(&mut deps, start + 100, 5000).unwrap();
+        assert_eq!(res.log[4], log("amount", 1000));
 
-        handle(&mut deps, third_party_env, msg).unwrap();
-
-        let sender = deps
-            .api
-            .canonical_address(&HumanAddr::from("sender"))
-            .unwrap();
-        let third_party = deps
-            .api
-            .canonical_address(&HumanAddr::from("third_party"))
-            .unwrap();
-
-        let balances = ReadOnlyBalances::new(&deps.storage);
-        let sender_balance = balances.get(&sender).unwrap();
-        assert_eq!(sender_balance, INITIAL_BALANCE - 1000);
-
-        let allowances = ReadOnlyAllowances::new(&sender, &deps.storage);
-        let allowance = allowances.get(&third_party).unwrap().unwrap();
-        assert_eq!(allowance.amount.u128(), ALLOWANCE_AMOUNT - 1000);
-
-        let state = State::read(&deps.storage).load().unwrap();
-        assert_eq!(state.total_supply.u128(), TOTAL_SUPPLY - 1000);
-    }
-
-    #[test]
-    fn handle_burn_from_too_many() {
+        // vesting locks shrink the partial fill instead of failing it
         let mut deps = mock_dependencies(16, &[]);
+        init_contract(&mut deps);
+        mint(&mut deps);
+        let msg = HandleMsg::SetAllowance {
+            spender: "third_party".into(),
+            amount: Uint128(50_000),
+            is_allowed: true,
+            expires: None,
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        let msg = HandleMsg::SetVesting {
+            address: "sender".into(),
+            locked: Uint128(INITIAL_BALANCE - 10_000),
+            start,
+            cliff: 100,
+            duration: 1000,
+        };
+        handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+        let res = partial_at(&mut deps, start + 10, 50_000).unwrap();
+        assert_eq!(res.log[4], log("amount", 10_000));
 
+        // and so does the daily transfer limit, which SpendableAllowance reports too
+        let mut deps = mock_dependencies(16, &[]);
         init_contract(&mut deps);
         mint(&mut deps);
         set_allowance(&mut deps);
+        let msg = HandleMsg::UpdateDailyLimit {
+            daily_transfer_limit: Some(Uint128(3_000)),
+        };
+        handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+        let spendable = |deps: &Extern<_, _, _>| -> Uint128 {
+            let msg = QueryMsg::SpendableAllowance {
+                owner: "sender".into(),
+                spender: "third_party".into(),
+                block: mock_env("sender", &[]).block,
+            };
+            from_binary(&query(deps, msg).unwrap()).unwrap()
+        };
+        assert_eq!(spendable(&deps), Uint128(3_000));
+        let res = partial_at(&mut deps, start, ALLOWANCE_AMOUNT).unwrap();
+        assert_eq!(res.log[4], log("amount", 3_000));
+        assert_eq!(spendable(&deps), Uint128(0));
+    }
 
-        let third_party_env = mock_env("third_party", &[]);
+    #[test]
+    fn every_transfer_entry_point_burns_the_fee() {
+        let cases = vec![
+            (
+                "sender",
+                HandleMsg::BatchTransfer {
+                    transfers: vec![
+                        TransferAction {
+                            to: "recipient".into(),
+                            amount: Uint128(5_000),
+                        },
+                        TransferAction {
+                            to: "recipient".into(),
+                            amount: Uint128(5_000),
+                        },
+                    ],
+                },
+                10_000,
+                200,
+            ),
+            (
+                "sender",
+                HandleMsg::TransferAll {
+                    to: "recipient".into(),
+                },
+                INITIAL_BALANCE - 100,
+                100,
+            ),
+            (
+                "sender",
+                HandleMsg::Send {
+                    contract: "recipient".into(),
+                    code_hash: "hash".into(),
+                    amount: Uint128(10_000),
+                    msg: Binary::from(b"payload".to_vec()),
+                },
+                10_000,
+                100,
+            ),
+            (
+                "third_party",
+                HandleMsg::TransferFrom {
+                    from: "sender".into(),
+                    to: "recipient".into(),
+                    amount: Uint128(5_000),
+                },
+                5_000,
+                100,
+            ),
+            (
+                "third_party",
+                HandleMsg::TransferFromPartial {
+                    from: "sender".into(),
+                    to: "recipient".into(),
+                    amount: Uint128(ALLOWANCE_AMOUNT),
+                },
+                ALLOWANCE_AMOUNT - 100,
+                100,
+            ),
+        ];
+
+        for (signer, msg, sent, burned) in cases {
+            let mut deps = mock_dependencies(16, &[]);
+            let init_msg = InitMsg {
+                burn_fee: Uint128(100),
+                ..init_msg()
+            };
+            init(&mut deps, mock_env("creator", &[]), init_msg).unwrap();
+            mint(&mut deps);
+            set_allowance(&mut deps);
+
+            let res = handle(&mut deps, mock_env(signer, &[]), msg).unwrap();
+            assert_eq!(res.log.last(), Some(&log("burn_fee", burned)));
+
+            let balances = ReadOnlyBalances::new(&deps.storage);
+            for (user, amount) in &[
+                ("sender", INITIAL_BALANCE - sent - burned),
+                ("recipient", sent),
+            ] {
+                let user = deps.api.canonical_address(&(*user).into()).unwrap();
+                assert_eq!(balances.get(&user).unwrap(), *amount);
+            }
+            let state = State::read(&deps.storage).load().unwrap();
+            assert_eq!(state.total_supply.u128(), TOTAL_SUPPLY - burned);
+            assert_supply_invariant(&deps);
+        }
 
-        let msg = HandleMsg::BurnFrom {
+        // the allowance pays for the burn fee along with the amount
+        let mut deps = mock_dependencies(16, &[]);
+        let init_msg = InitMsg {
+            burn_fee: Uint128(100),
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("creator", &[]), init_msg).unwrap();
+        mint(&mut deps);
+        set_allowance(&mut deps);
+        let msg = HandleMsg::TransferFrom {
             from: "sender".into(),
-            amount: Uint128(ALLOWANCE_AMOUNT * 2),
+            to: "recipient".into(),
+            amount: Uint128(ALLOWANCE_AMOUNT),
         };
-
-        handle(&mut deps, third_party_env, msg).unwrap_err();
+        let err = handle(&mut deps, mock_env("third_party", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientAllowance.into());
     }
 
     #[test]
-    fn handle_burn_from_unauthorized() {
+    fn burn_fee_cannot_be_paid_from_locked_tokens() {
         let mut deps = mock_dependencies(16, &[]);
 
-        init_contract(&mut deps);
+        let msg = InitMsg {
+            burn_fee: Uint128(100),
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("creator", &[]), msg).unwrap();
         mint(&mut deps);
 
-        let third_party_env = mock_env("third_party", &[]);
+        let env = mock_env("minter", &[]);
+        let msg = HandleMsg::SetVesting {
+            address: "sender".into(),
+            locked: Uint128(INITIAL_BALANCE - 10_000),
+            start: env.block.time,
+            cliff: 100,
+            duration: 1000,
+        };
+        handle(&mut deps, env, msg).unwrap();
 
-        let msg = HandleMsg::BurnFrom {
-            from: "sender".into(),
-            amount: Uint128(1000),
+        // the whole unlocked balance can be sent, but then nothing is left for the fee
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(10_000),
         };
+        let err = handle(&mut deps, mock_env("sender", &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientFunds {
+                balance: 10_000,
+                required: 10_100,
+            }
+            .into()
+        );
 
-        let err = handle(&mut deps, third_party_env, msg).unwrap_err();
-        assert_eq!(err, StdError::unauthorized());
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(9_900),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        let state = State::read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_supply.u128(), TOTAL_SUPPLY - 100);
     }
 
     #[test]
-    fn handle_burn_from_allowance_is_false() {
+    fn fee_bearing_transfer_notifies_hooks_once() {
         let mut deps = mock_dependencies(16, &[]);
 
-        init_contract(&mut deps);
+        let msg = InitMsg {
+            fee_bps: Some(100),
+            fee_collector: Some("collector".into()),
+            burn_fee: Uint128(5),
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("creator", &[]), msg).unwrap();
         mint(&mut deps);
 
-        // set allowance
-        let msg = HandleMsg::SetAllowance {
-            spender: "third_party".into(),
-            amount: Uint128(ALLOWANCE_AMOUNT),
-            is_allowed: false,
+        let msg = HandleMsg::AddHook {
+            contract: "analytics".into(),
+            code_hash: "analytics_hash".into(),
         };
+        handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
 
-        let env = mock_env("sender", &[]);
+        let msg = HandleMsg::Transfer {
+            to: "recipient".into(),
+            amount: Uint128(10_000),
+        };
+        let resp = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
 
-        handle(&mut deps, env, msg).unwrap();
+        let hook_msg = to_binary(&TransferHookMsg::TransferHook {
+            from: "sender".into(),
+            to: "recipient".into(),
+            amount: Uint128(10_000),
+            fee: Uint128(100),
+            burn_fee: Uint128(5),
+        })
+        .unwrap();
+        assert_eq!(
+            resp.messages,
+            vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "analytics".into(),
+                callback_code_hash: "analytics_hash".into(),
+                msg: hook_msg,
+                send: vec![],
+            })]
+        );
+    }
 
-        // burn from
-        let third_party_env = mock_env("third_party", &[]);
+    #[test]
+    fn cooldown_only_starts_for_the_net_recipient() {
+        let mut deps = mock_dependencies(16, &[]);
 
-        let msg = HandleMsg::BurnFrom {
-            from: "sender".into(),
-            amount: Uint128(1000),
+        let msg = InitMsg {
+            fee_bps: Some(100),
+            fee_collector: Some("collector".into()),
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("creator", &[]), msg).unwrap();
+        mint(&mut deps);
+        let msg = HandleMsg::UpdateCooldown {
+            cooldown_blocks: Some(10),
+        };
+        handle(&mut deps, mock_env("minter", &[]), msg).unwrap();
+
+        let transfer = |deps: &mut Extern<_, _, _>, from: &str, to: &str, amount: u128| {
+            let msg = HandleMsg::Transfer {
+                to: to.into(),
+                amount: Uint128(amount),
+            };
+            handle(deps, mock_env(from, &[]), msg)
         };
 
-        let err = handle(&mut deps, third_party_env, msg).unwrap_err();
-        assert_eq!(err, StdError::unauthorized());
+        // a self-transfer does not lock the sender out of its own balance
+        transfer(&mut deps, "sender", "sender", 1000).unwrap();
+        transfer(&mut deps, "sender", "recipient", 1000).unwrap();
+
+        // collecting the fee does not put the collector into a cooldown
+        transfer(&mut deps, "collector", "treasury", 20).unwrap();
+
+        let err = transfer(&mut deps, "recipient", "treasury", 100).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(format!(
+                "Sender is in a transfer cooldown until height {}",
+                mock_env("recipient", &[]).block.height + 10
+            ))
+        );
     }
 
     #[test]
-    fn query_get_balance() {
+    fn send_to_burn_address_pays_transfer_fees() {
         let mut deps = mock_dependencies(16, &[]);
 
-        init_contract(&mut deps);
+        let msg = InitMsg {
+            fee_bps: Some(100),
+            fee_collector: Some("collector".into()),
+            burn_fee: Uint128(5),
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("creator", &[]), msg).unwrap();
         mint(&mut deps);
 
-        let msg = QueryMsg::GetBalance {
-            user: "sender".into(),
+        let msg = HandleMsg::SendToBurnAddress {
+            amount: Uint128(1000),
         };
-
-        let resp = query(&mut deps, msg).unwrap();
+        let res = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        assert_eq!(
+            res.log,
+            vec![
+                log(ACTION, "send_to_burn_address"),
+                log(FROM, "sender"),
+                log(AMOUNT, 1000),
+                log("fee", 10),
+                log("burn_fee", 5),
+            ]
+        );
+
+        let resp = query(&deps, QueryMsg::BurnedBalance {}).unwrap();
         let resp: BalanceResponse = from_binary(&resp).unwrap();
-        assert_eq!(resp.amount.u128(), INITIAL_BALANCE);
+        assert_eq!(resp.amount, Uint128(990));
+        let balance = |user: &str| {
+            let user = deps.api.canonical_address(&user.into()).unwrap();
+            ReadOnlyBalances::new(&deps.storage).get(&user).unwrap()
+        };
+        assert_eq!(balance("collector"), 10);
+        assert_eq!(balance("sender"), INITIAL_BALANCE - 1005);
+        assert_supply_invariant(&deps);
     }
 }
@@ -0,0 +1,33 @@
+use cosmwasm_std::StdError;
+use snafu::Snafu;
+
+#[derive(Snafu, Debug, PartialEq)]
+#[snafu(visibility = "pub")]
+pub enum ContractError {
+    #[snafu(display("insufficient funds: balance {}, required {}", balance, required))]
+    InsufficientFunds { balance: u128, required: u128 },
+
+    #[snafu(display("insufficient allowance"))]
+    InsufficientAllowance,
+
+    #[snafu(display("unauthorized"))]
+    Unauthorized,
+
+    #[snafu(display("Amount overflow"))]
+    Overflow,
+
+    #[snafu(display("Amount underflow"))]
+    Underflow,
+
+    #[snafu(display("contract is paused"))]
+    Paused,
+}
+
+impl From<ContractError> for StdError {
+    fn from(err: ContractError) -> Self {
+        match err {
+            ContractError::Unauthorized => StdError::unauthorized(),
+            err => StdError::generic_err(err.to_string()),
+        }
+    }
+}
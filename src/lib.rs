@@ -1,6 +1,9 @@
+mod attributes;
 pub mod contract;
+pub mod error;
+mod math;
 pub mod msg;
 pub mod state;
 
 #[cfg(target_arch = "wasm32")]
-cosmwasm_std::create_entry_points!(contract);
+cosmwasm_std::create_entry_points_with_migration!(contract);
@@ -0,0 +1,133 @@
+use cosmwasm_std::{StdError, StdResult, Uint128};
+
+use crate::error::ContractError;
+
+pub fn add_amount(a: u128, b: u128) -> StdResult<u128> {
+    a.checked_add(b)
+        .ok_or_else(|| ContractError::Overflow.into())
+}
+
+pub fn sub_amount(a: u128, b: u128) -> StdResult<u128> {
+    a.checked_sub(b)
+        .ok_or_else(|| ContractError::Underflow.into())
+}
+
+// Uint128 has no checked arithmetic in this cosmwasm version, so wrapped amounts go through here
+pub fn add_uint128(a: Uint128, b: Uint128) -> StdResult<Uint128> {
+    add_amount(a.u128(), b.u128()).map(Uint128)
+}
+
+pub fn sub_uint128(a: Uint128, b: Uint128) -> StdResult<Uint128> {
+    sub_amount(a.u128(), b.u128()).map(Uint128)
+}
+
+// takes amount out of a holder's balance, failing the way every spend path reports a shortfall
+pub fn debit(balance: u128, amount: u128) -> StdResult<u128> {
+    balance.checked_sub(amount).ok_or_else(|| {
+        ContractError::InsufficientFunds {
+            balance,
+            required: amount,
+        }
+        .into()
+    })
+}
+
+// floor(a * b / d) without a 256-bit intermediate: the a % d part is multiplied
+// bit by bit, keeping the running remainder below d so nothing can overflow
+pub fn mul_div(a: u128, b: u128, d: u128) -> StdResult<u128> {
+    if d == 0 {
+        return Err(StdError::generic_err("division by zero"));
+    }
+
+    let (q, r) = (a / d, a % d);
+    let high = q.checked_mul(b).ok_or(ContractError::Overflow)?;
+
+    // low stays below the processed prefix of b, so it can't overflow either
+    let (mut low, mut rem) = (0u128, 0u128);
+    for bit in (0..128 - b.leading_zeros()).rev() {
+        low *= 2;
+        if rem >= d - rem {
+            rem -= d - rem;
+            low += 1;
+        } else {
+            rem *= 2;
+        }
+        if (b >> bit) & 1 == 1 {
+            if rem >= d - r {
+                rem -= d - r;
+                low += 1;
+            } else {
+                rem += r;
+            }
+        }
+    }
+
+    add_amount(high, low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_amount_overflow() {
+        assert_eq!(add_amount(1, 2).unwrap(), 3);
+
+        let err = add_amount(u128::MAX, 1).unwrap_err();
+        assert_eq!(err, ContractError::Overflow.into());
+    }
+
+    #[test]
+    fn sub_amount_underflow() {
+        assert_eq!(sub_amount(3, 2).unwrap(), 1);
+
+        let err = sub_amount(1, 2).unwrap_err();
+        assert_eq!(err, ContractError::Underflow.into());
+    }
+
+    #[test]
+    fn uint128_helpers_match_raw_arithmetic() {
+        assert_eq!(add_uint128(Uint128(1), Uint128(2)).unwrap(), Uint128(3));
+        assert_eq!(
+            add_uint128(Uint128(u128::MAX), Uint128(1)).unwrap_err(),
+            add_amount(u128::MAX, 1).unwrap_err()
+        );
+
+        assert_eq!(sub_uint128(Uint128(3), Uint128(2)).unwrap(), Uint128(1));
+        assert_eq!(
+            sub_uint128(Uint128(1), Uint128(2)).unwrap_err(),
+            sub_amount(1, 2).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn debit_insufficient_funds() {
+        assert_eq!(debit(5, 5).unwrap(), 0);
+        assert_eq!(debit(5, 2).unwrap(), 3);
+
+        let err = debit(1, 2).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientFunds {
+                balance: 1,
+                required: 2,
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn mul_div_exact() {
+        assert_eq!(mul_div(25, 10_000, 100).unwrap(), 2500);
+        assert_eq!(mul_div(1, 2, 3).unwrap(), 0);
+        assert_eq!(mul_div(u128::MAX, 10_000, u128::MAX).unwrap(), 10_000);
+        assert_eq!(mul_div(u128::MAX / 4, 10_000, u128::MAX).unwrap(), 2499);
+        assert_eq!(mul_div(u128::MAX / 2, 3, u128::MAX / 2).unwrap(), 3);
+
+        assert_eq!(
+            mul_div(u128::MAX, 2, 1).unwrap_err(),
+            ContractError::Overflow.into()
+        );
+        assert!(mul_div(1, 1, 0).is_err());
+    }
+}
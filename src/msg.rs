@@ -1,13 +1,161 @@
-use cosmwasm_std::{HumanAddr, Uint128};
+use std::fmt;
+
+use cosmwasm_std::{Binary, BlockInfo, HumanAddr, StdError, StdResult, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InitMsg {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
     pub minter: HumanAddr,
-    pub total_supply: Uint128,
+    pub total_supply: Option<Uint128>,
+    pub cap: Option<Uint128>,
+    pub marketing: Option<HumanAddr>,
+    pub initial_balances: Vec<InitBalance>,
+    pub init_recipient: Option<HumanAddr>,
+    pub fee_bps: Option<u16>,
+    pub fee_collector: Option<HumanAddr>,
+    pub admin: Option<HumanAddr>,
+    pub max_mint_per_tx: Option<Uint128>,
+    pub min_transfer_amount: Option<Uint128>,
+    pub burnable_by_holders: Option<bool>,
+    pub default_allowance_ttl: Option<u64>,
+    #[serde(default)]
+    pub initial_frozen: Vec<HumanAddr>,
+    #[serde(default)]
+    pub fee_rounding: Rounding,
+    // burned from the sender on every transfer, on top of the amount sent
+    #[serde(default)]
+    pub burn_fee: Uint128,
+}
+
+impl InitMsg {
+    pub fn validate(&self) -> StdResult<()> {
+        validate_token_info(&self.name, &self.symbol, self.decimals)?;
+
+        if self.minter.as_str().is_empty() {
+            return Err(StdError::generic_err("Minter address must not be empty"));
+        }
+
+        if let (Some(total_supply), Some(cap)) = (self.total_supply, self.cap) {
+            if total_supply > cap {
+                return Err(StdError::generic_err("Initial supply exceeds cap"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_token_info(name: &str, symbol: &str, decimals: u8) -> StdResult<()> {
+    if name.is_empty() {
+        return Err(StdError::generic_err("Token name must not be empty"));
+    }
+
+    if !is_valid_symbol(symbol) {
+        return Err(StdError::generic_err(
+            "Ticker symbol must be 3-12 alphanumeric characters",
+        ));
+    }
+
+    if decimals > 18 {
+        return Err(StdError::generic_err("Decimals must not exceed 18"));
+    }
+
+    Ok(())
+}
+
+fn is_valid_symbol(symbol: &str) -> bool {
+    (3..=12).contains(&symbol.len()) && symbol.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InitBalance {
+    pub address: HumanAddr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    // required when migrating from the original release, which stored no token metadata
+    #[serde(default)]
+    pub token_info: Option<TokenMetadata>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl TokenMetadata {
+    pub fn validate(&self) -> StdResult<()> {
+        validate_token_info(&self.name, &self.symbol, self.decimals)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Logo {
+    Url(String),
+    Embedded(Binary),
+}
+
+// how the fractional part of a transfer fee is resolved
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    Down,
+    Up,
+    // half-way remainders round to the even fee
+    Nearest,
+}
+
+impl Default for Rounding {
+    fn default() -> Self {
+        Rounding::Down
+    }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Expiration {
+    AtHeight(u64),
+    AtTime(u64),
+    Never,
+}
+
+impl Expiration {
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        match self {
+            Expiration::AtHeight(height) => block.height >= *height,
+            Expiration::AtTime(time) => block.time >= *time,
+            Expiration::Never => false,
+        }
+    }
+}
+
+impl Default for Expiration {
+    fn default() -> Self {
+        Expiration::Never
+    }
+}
+
+impl fmt::Display for Expiration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expiration::AtHeight(height) => write!(f, "expiration height: {}", height),
+            Expiration::AtTime(time) => write!(f, "expiration time: {}", time),
+            Expiration::Never => write!(f, "expiration: never"),
+        }
+    }
+}
+
+// Amounts are decimal strings. Empty, negative or fractional ones fail to parse into
+// a `StdError::ParseErr` before any handler runs, so handlers never see them.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum HandleMsg {
@@ -15,19 +163,82 @@ pub enum HandleMsg {
         to: HumanAddr,
         amount: Uint128,
     },
+    BatchTransfer {
+        transfers: Vec<TransferAction>,
+    },
+    TransferAll {
+        to: HumanAddr,
+    },
+    TransferWithMemo {
+        to: HumanAddr,
+        amount: Uint128,
+        memo: String,
+    },
+    TransferToExisting {
+        to: HumanAddr,
+        amount: Uint128,
+    },
+    TransferEnsureBalance {
+        to: HumanAddr,
+        amount: Uint128,
+        min_resulting_balance: Uint128,
+    },
     Burn {
         amount: Uint128,
     },
+    BurnAll {},
+    // moves tokens to the unspendable burn address, leaving total supply untouched
+    SendToBurnAddress {
+        amount: Uint128,
+    },
+    Send {
+        contract: HumanAddr,
+        code_hash: String,
+        amount: Uint128,
+        msg: Binary,
+    },
     SetAllowance {
         spender: HumanAddr,
         amount: Uint128,
         is_allowed: bool,
+        expires: Option<Expiration>,
+    },
+    BatchSetAllowance {
+        allowances: Vec<AllowanceAction>,
+    },
+    // lets `spender` draw up to `amount` per `period` seconds, refilling at each period boundary
+    SetRecurringAllowance {
+        spender: HumanAddr,
+        amount: Uint128,
+        period: u64,
+    },
+    IncreaseAllowance {
+        spender: HumanAddr,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    DecreaseAllowance {
+        spender: HumanAddr,
+        amount: Uint128,
+    },
+    RevokeAllowance {
+        spender: HumanAddr,
+    },
+    PruneAllowance {
+        owner: HumanAddr,
+        spender: HumanAddr,
     },
     TransferFrom {
         from: HumanAddr,
         to: HumanAddr,
         amount: Uint128,
     },
+    // moves as much of `amount` as both the allowance and the owner's balance permit
+    TransferFromPartial {
+        from: HumanAddr,
+        to: HumanAddr,
+        amount: Uint128,
+    },
     BurnFrom {
         from: HumanAddr,
         amount: Uint128,
@@ -36,15 +247,464 @@ pub enum HandleMsg {
         recipient: HumanAddr,
         amount: Uint128,
     },
+    BatchMint {
+        mints: Vec<MintAction>,
+    },
+    // mints `total` across current holders in proportion to their balances
+    DistributeProRata {
+        total: Uint128,
+    },
+    MintOnce {
+        recipient: HumanAddr,
+        amount: Uint128,
+        nonce: String,
+    },
+    SetPaused {
+        paused: bool,
+    },
+    UpdateMinter {
+        new_minter: Option<HumanAddr>,
+    },
+    AddMinter {
+        minter: HumanAddr,
+    },
+    RemoveMinter {
+        minter: HumanAddr,
+    },
+    UpdateMarketing {
+        project: Option<String>,
+        description: Option<String>,
+        marketing: Option<HumanAddr>,
+    },
+    UploadLogo {
+        logo: Logo,
+    },
+    UpdateAdmin {
+        new_admin: HumanAddr,
+    },
+    UpdateFee {
+        fee_bps: u16,
+        fee_collector: HumanAddr,
+        // keeps the current rounding mode when omitted
+        #[serde(default)]
+        fee_rounding: Option<Rounding>,
+    },
+    UpdateMintLimit {
+        max_mint_per_tx: Option<Uint128>,
+    },
+    // caps how much any single address may move out per day; `None` removes the cap
+    UpdateDailyLimit {
+        daily_transfer_limit: Option<Uint128>,
+    },
+    // blocks an address from sending for this many blocks after it last received a transfer;
+    // `None` turns the cooldown off
+    UpdateCooldown {
+        cooldown_blocks: Option<u64>,
+    },
+    Freeze {
+        address: HumanAddr,
+    },
+    Unfreeze {
+        address: HumanAddr,
+    },
+    ForceTransfer {
+        from: HumanAddr,
+        to: HumanAddr,
+        amount: Uint128,
+    },
+    Snapshot {},
+    // recovers native coins sent to the contract by mistake; token balances are untouched
+    Sweep {
+        denom: String,
+        recipient: HumanAddr,
+    },
+    AddHook {
+        contract: HumanAddr,
+        code_hash: String,
+    },
+    RemoveHook {
+        contract: HumanAddr,
+    },
+    SetVesting {
+        address: HumanAddr,
+        locked: Uint128,
+        start: u64,
+        cliff: u64,
+        duration: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferAction {
+    pub to: HumanAddr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintAction {
+    pub recipient: HumanAddr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowanceAction {
+    pub spender: HumanAddr,
+    pub amount: Uint128,
+    pub is_allowed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiverHandleMsg {
+    Receive {
+        sender: HumanAddr,
+        amount: Uint128,
+        msg: Binary,
+    },
+}
+
+// sent to every registered hook contract after each transfer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferHookMsg {
+    TransferHook {
+        from: HumanAddr,
+        to: HumanAddr,
+        // the full amount debited, `fee` of which went to the fee collector
+        amount: Uint128,
+        fee: Uint128,
+        // burned from the sender on top of `amount`
+        burn_fee: Uint128,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    GetBalance { user: HumanAddr },
+    GetBalance {
+        user: HumanAddr,
+    },
+    BurnedBalance {},
+    BalanceAt {
+        user: HumanAddr,
+        height: u64,
+    },
+    Version {},
+    BalanceAndSupply {
+        user: HumanAddr,
+    },
+    MinTransferAmount {},
+    HolderCount {},
+    // the user's balance as basis points of the total supply
+    SupplyShare {
+        user: HumanAddr,
+    },
+    #[cfg(feature = "debug")]
+    RawState {
+        prefix: String,
+        start_after: Option<Binary>,
+        limit: u32,
+    },
+    SpendableAllowance {
+        owner: HumanAddr,
+        spender: HumanAddr,
+        block: BlockInfo,
+    },
+    FormattedBalance {
+        user: HumanAddr,
+    },
+    Balances {
+        users: Vec<HumanAddr>,
+    },
+    TokenInfo {},
+    Config {},
+    // queries have no env, so `is_expired` is only computed when the caller passes its block
+    Allowance {
+        owner: HumanAddr,
+        spender: HumanAddr,
+        #[serde(default)]
+        block: Option<BlockInfo>,
+    },
+    Minter {},
+    AllMinters {},
+    IsMinter {
+        address: HumanAddr,
+    },
+    Admin {},
+    AllAccounts {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    // walks every balance, so gas grows with the number of holders; `limit` is capped at 100
+    TopHolders {
+        limit: u32,
+    },
+    AllAllowances {
+        owner: HumanAddr,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    // sums every active, unexpired allowance the owner has granted as of `block`; any
+    // unlimited one makes the total u128::MAX
+    TotalAllowanceGranted {
+        owner: HumanAddr,
+        block: BlockInfo,
+    },
+    AllowancesBySpender {
+        spender: HumanAddr,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    MarketingInfo {},
+    DownloadLogo {},
+    IsFrozen {
+        address: HumanAddr,
+    },
+    VestedAmount {
+        address: HumanAddr,
+        block: BlockInfo,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BalanceResponse {
     pub amount: Uint128,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BalanceAndSupplyResponse {
+    pub balance: Uint128,
+    pub total_supply: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FormattedBalanceResponse {
+    pub raw: Uint128,
+    pub display: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowanceResponse {
+    pub amount: Uint128,
+    pub is_allowed: bool,
+    pub expires: Expiration,
+    pub is_expired: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenInfoResponse {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub total_supply: Uint128,
+    pub total_supply_display: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub total_supply: Uint128,
+    pub cap: Option<Uint128>,
+    pub minters: Vec<HumanAddr>,
+    pub admin: HumanAddr,
+    pub paused: bool,
+    pub fee_bps: u16,
+    pub fee_rounding: Rounding,
+    pub fee_collector: HumanAddr,
+    pub max_mint_per_tx: Option<Uint128>,
+    pub min_transfer_amount: Option<Uint128>,
+    pub burnable_by_holders: bool,
+    pub default_allowance_ttl: Option<u64>,
+    pub daily_transfer_limit: Option<Uint128>,
+    pub cooldown_blocks: Option<u64>,
+    pub burn_fee: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MinterResponse {
+    pub cap: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MinTransferAmountResponse {
+    pub min_transfer_amount: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AdminResponse {
+    pub admin: HumanAddr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VersionResponse {
+    pub contract: String,
+    pub version: String,
+}
+
+#[cfg(feature = "debug")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RawEntry {
+    pub key: Binary,
+    pub value: Binary,
+}
+
+#[cfg(feature = "debug")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RawStateResponse {
+    pub entries: Vec<RawEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllAccountsResponse {
+    pub accounts: Vec<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HolderBalance {
+    pub address: HumanAddr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TopHoldersResponse {
+    pub holders: Vec<HolderBalance>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowanceInfo {
+    pub spender: HumanAddr,
+    pub amount: Uint128,
+    pub is_allowed: bool,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllAllowancesResponse {
+    pub allowances: Vec<AllowanceInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SpenderAllowanceInfo {
+    pub owner: HumanAddr,
+    pub amount: Uint128,
+    pub is_allowed: bool,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowancesBySpenderResponse {
+    pub allowances: Vec<SpenderAllowanceInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LogoInfo {
+    Url(String),
+    Embedded,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MarketingInfoResponse {
+    pub project: Option<String>,
+    pub description: Option<String>,
+    pub marketing: Option<HumanAddr>,
+    pub logo: Option<LogoInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DownloadLogoResponse {
+    pub mime_type: String,
+    pub data: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsFrozenResponse {
+    pub frozen: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestedAmountResponse {
+    pub locked: Uint128,
+    pub vested: Uint128,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_env;
+
+    #[test]
+    fn expiration_at_height() {
+        let block = mock_env("anyone", &[]).block;
+
+        assert!(!Expiration::AtHeight(block.height + 1).is_expired(&block));
+        assert!(Expiration::AtHeight(block.height).is_expired(&block));
+        assert_eq!(
+            Expiration::AtHeight(12).to_string(),
+            "expiration height: 12"
+        );
+    }
+
+    #[test]
+    fn expiration_at_time() {
+        let block = mock_env("anyone", &[]).block;
+
+        assert!(!Expiration::AtTime(block.time + 1).is_expired(&block));
+        assert!(Expiration::AtTime(block.time).is_expired(&block));
+        assert_eq!(Expiration::AtTime(34).to_string(), "expiration time: 34");
+    }
+
+    #[test]
+    fn expiration_never() {
+        let block = BlockInfo {
+            height: u64::MAX,
+            time: u64::MAX,
+            ..mock_env("anyone", &[]).block
+        };
+
+        assert!(!Expiration::Never.is_expired(&block));
+        assert_eq!(Expiration::default(), Expiration::Never);
+        assert_eq!(Expiration::Never.to_string(), "expiration: never");
+    }
+
+    #[test]
+    fn schemas_generate() {
+        use cosmwasm_schema::schema_for;
+        use cosmwasm_std::to_vec;
+
+        // keep in step with examples/schema.rs
+        let schemas = vec![
+            schema_for!(InitMsg),
+            schema_for!(HandleMsg),
+            schema_for!(QueryMsg),
+            schema_for!(MigrateMsg),
+            schema_for!(ReceiverHandleMsg),
+            schema_for!(TransferHookMsg),
+            schema_for!(BalanceResponse),
+            schema_for!(BalanceAndSupplyResponse),
+            schema_for!(FormattedBalanceResponse),
+            schema_for!(AllowanceResponse),
+            schema_for!(TokenInfoResponse),
+            schema_for!(ConfigResponse),
+            schema_for!(MinterResponse),
+            schema_for!(AdminResponse),
+            schema_for!(MinTransferAmountResponse),
+            schema_for!(AllAccountsResponse),
+            schema_for!(TopHoldersResponse),
+            schema_for!(AllAllowancesResponse),
+            schema_for!(AllowancesBySpenderResponse),
+            schema_for!(MarketingInfoResponse),
+            schema_for!(DownloadLogoResponse),
+            schema_for!(IsFrozenResponse),
+            schema_for!(VersionResponse),
+            schema_for!(VestedAmountResponse),
+            #[cfg(feature = "debug")]
+            schema_for!(RawStateResponse),
+        ];
+        for schema in &schemas {
+            assert!(!to_vec(schema).unwrap().is_empty());
+        }
+    }
+}
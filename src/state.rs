@@ -1,66 +1,351 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{
-    from_slice, to_vec, CanonicalAddr, HumanAddr, ReadonlyStorage, StdResult, Storage, Uint128,
+    from_slice, to_vec, Api, BlockInfo, CanonicalAddr, HumanAddr, Order, ReadonlyStorage, StdError,
+    StdResult, Storage, Uint128,
 };
 use cosmwasm_storage::{
     singleton, singleton_read, PrefixedStorage, ReadonlyPrefixedStorage, ReadonlySingleton,
     Singleton,
 };
 
-const STATE_KEY: &[u8] = b"state";
-const BALANCES_KEY: &[u8] = b"balances";
-const ALLOWANCES_KEY: &[u8] = b"allowances";
+use crate::msg::{Expiration, Logo, Rounding};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+// every top-level storage namespace, kept in one place so new prefixes can't silently alias
+#[derive(Clone, Copy, Debug)]
+enum StorageKey {
+    State,
+    Balances,
+    BalanceHistory,
+    Checkpoints,
+    Allowances,
+    Minters,
+    Frozen,
+    Marketing,
+    ContractVersion,
+    MintNonces,
+    HolderCount,
+    Vesting,
+    TransferHooks,
+    SpenderIndex,
+    Outflows,
+    LastReceived,
+}
+
+impl StorageKey {
+    #[cfg(test)]
+    const ALL: [StorageKey; 16] = [
+        StorageKey::State,
+        StorageKey::Balances,
+        StorageKey::BalanceHistory,
+        StorageKey::Checkpoints,
+        StorageKey::Allowances,
+        StorageKey::Minters,
+        StorageKey::Frozen,
+        StorageKey::Marketing,
+        StorageKey::ContractVersion,
+        StorageKey::MintNonces,
+        StorageKey::HolderCount,
+        StorageKey::Vesting,
+        StorageKey::TransferHooks,
+        StorageKey::SpenderIndex,
+        StorageKey::Outflows,
+        StorageKey::LastReceived,
+    ];
+
+    fn as_bytes(self) -> &'static [u8] {
+        match self {
+            StorageKey::State => b"state",
+            StorageKey::Balances => b"balances",
+            StorageKey::BalanceHistory => b"balance_history",
+            StorageKey::Checkpoints => b"checkpoints",
+            StorageKey::Allowances => b"allowances",
+            StorageKey::Minters => b"minters",
+            StorageKey::Frozen => b"frozen",
+            StorageKey::Marketing => b"marketing",
+            StorageKey::ContractVersion => b"contract_version",
+            StorageKey::MintNonces => b"mint_nonces",
+            StorageKey::HolderCount => b"holder_count",
+            StorageKey::Vesting => b"vesting",
+            StorageKey::TransferHooks => b"transfer_hooks",
+            StorageKey::SpenderIndex => b"spender_index",
+            StorageKey::Outflows => b"outflows",
+            StorageKey::LastReceived => b"last_received",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
-    pub minter: HumanAddr,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
     pub total_supply: Uint128,
+    pub cap: Option<Uint128>,
+    pub paused: bool,
+    pub fee_bps: u16,
+    #[serde(default)]
+    pub fee_rounding: Rounding,
+    pub fee_collector: HumanAddr,
+    pub admin: HumanAddr,
+    pub max_mint_per_tx: Option<Uint128>,
+    pub min_transfer_amount: Option<Uint128>,
+    #[serde(default = "default_burnable_by_holders")]
+    pub burnable_by_holders: bool,
+    pub default_allowance_ttl: Option<u64>,
+    pub daily_transfer_limit: Option<Uint128>,
+    // all-zero address no key can sign for, used by SendToBurnAddress; migrate fills it
+    // in for states stored without one
+    #[serde(default)]
+    pub burn_address: CanonicalAddr,
+    pub cooldown_blocks: Option<u64>,
+    #[serde(default)]
+    pub burn_fee: Uint128,
+}
+
+fn default_burnable_by_holders() -> bool {
+    true
 }
 
 impl State {
     pub fn write<S: Storage>(storage: &mut S) -> Singleton<S, Self> {
-        singleton(storage, STATE_KEY)
+        singleton(storage, StorageKey::State.as_bytes())
+    }
+
+    pub fn read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Self> {
+        singleton_read(storage, StorageKey::State.as_bytes())
+    }
+}
+
+// the state singleton as the original release stored it, before the contract was versioned
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BaselineState {
+    pub minter: HumanAddr,
+    pub total_supply: Uint128,
+}
+
+impl BaselineState {
+    pub fn read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Self> {
+        singleton_read(storage, StorageKey::State.as_bytes())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ContractVersion {
+    pub contract: String,
+    pub version: String,
+}
+
+impl ContractVersion {
+    pub fn write<S: Storage>(storage: &mut S) -> Singleton<S, Self> {
+        singleton(storage, StorageKey::ContractVersion.as_bytes())
+    }
+
+    pub fn read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Self> {
+        singleton_read(storage, StorageKey::ContractVersion.as_bytes())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct MarketingInfo {
+    pub project: Option<String>,
+    pub description: Option<String>,
+    pub marketing: Option<HumanAddr>,
+    pub logo: Option<Logo>,
+}
+
+impl MarketingInfo {
+    pub fn write<S: Storage>(storage: &mut S) -> Singleton<S, Self> {
+        singleton(storage, StorageKey::Marketing.as_bytes())
+    }
+
+    pub fn read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Self> {
+        singleton_read(storage, StorageKey::Marketing.as_bytes())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct Checkpoints {
+    pub heights: Vec<u64>,
+}
+
+impl Checkpoints {
+    pub fn write<S: Storage>(storage: &mut S) -> Singleton<S, Self> {
+        singleton(storage, StorageKey::Checkpoints.as_bytes())
+    }
+
+    pub fn read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Self> {
+        singleton_read(storage, StorageKey::Checkpoints.as_bytes())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Hook {
+    pub contract: HumanAddr,
+    pub code_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct TransferHooks {
+    pub hooks: Vec<Hook>,
+}
+
+impl TransferHooks {
+    pub fn write<S: Storage>(storage: &mut S) -> Singleton<S, Self> {
+        singleton(storage, StorageKey::TransferHooks.as_bytes())
     }
 
     pub fn read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Self> {
-        singleton_read(storage, STATE_KEY)
+        singleton_read(storage, StorageKey::TransferHooks.as_bytes())
     }
 }
 
 pub struct Balances<'a, S: Storage> {
-    storage: PrefixedStorage<'a, S>,
+    storage: &'a mut S,
 }
 
 impl<'a, S: Storage> Balances<'a, S> {
     pub fn new(storage: &'a mut S) -> Self {
-        let storage = PrefixedStorage::new(BALANCES_KEY, storage);
         Self { storage }
     }
 
     pub fn set(&mut self, addr: &CanonicalAddr, amount: u128) -> StdResult<()> {
-        self.storage
-            .set(addr.as_slice(), &to_vec(&Uint128(amount))?);
+        // emptied accounts are dropped so ranging over balances only yields holders
+        if amount == 0 {
+            return self.remove(addr);
+        }
+
+        let previous = self.record_checkpoint(addr)?;
+        if previous == 0 {
+            self.update_holder_count(|count| count.checked_add(1))?;
+        }
+        let mut balances =
+            PrefixedStorage::new(StorageKey::Balances.as_bytes(), &mut *self.storage);
+        balances.set(addr.as_slice(), &to_vec(&Uint128(amount))?);
         Ok(())
     }
 
+    pub fn remove(&mut self, addr: &CanonicalAddr) -> StdResult<()> {
+        let previous = self.record_checkpoint(addr)?;
+        if previous != 0 {
+            self.update_holder_count(|count| count.checked_sub(1))?;
+        }
+        let mut balances =
+            PrefixedStorage::new(StorageKey::Balances.as_bytes(), &mut *self.storage);
+        balances.remove(addr.as_slice());
+        Ok(())
+    }
+
+    fn update_holder_count(&mut self, update: impl FnOnce(u64) -> Option<u64>) -> StdResult<()> {
+        // a counter that predates existing balances reads as 0; migrate rebuilds it
+        let count = update(holder_count(self.storage)?).ok_or_else(|| {
+            StdError::generic_err("Holder count is out of sync, migrate to rebuild it")
+        })?;
+        singleton(self.storage, StorageKey::HolderCount.as_bytes()).save(&count)
+    }
+
+    fn record_checkpoint(&mut self, addr: &CanonicalAddr) -> StdResult<u128> {
+        let previous = self.get(addr)?;
+
+        // the first change after a checkpoint preserves the balance it had at that checkpoint
+        let checkpoint = Checkpoints::read(self.storage)
+            .may_load()?
+            .and_then(|checkpoints| checkpoints.heights.last().copied());
+        if let Some(checkpoint) = checkpoint {
+            let mut history = PrefixedStorage::multilevel(
+                &[StorageKey::BalanceHistory.as_bytes(), addr.as_slice()],
+                &mut *self.storage,
+            );
+            let key = checkpoint.to_be_bytes();
+            if history.get(&key).is_none() {
+                history.set(&key, &to_vec(&Uint128(previous))?);
+            }
+        }
+        Ok(previous)
+    }
+
     pub fn get(&self, addr: &CanonicalAddr) -> StdResult<u128> {
-        ReadOnlyBalancesImpl(&self.storage).get(addr)
+        ReadOnlyBalances::new(&*self.storage).get(addr)
+    }
+
+    pub fn exists(&self, addr: &CanonicalAddr) -> bool {
+        ReadOnlyBalances::new(&*self.storage).exists(addr)
+    }
+}
+
+pub fn holder_count<S: ReadonlyStorage>(storage: &S) -> StdResult<u64> {
+    Ok(singleton_read(storage, StorageKey::HolderCount.as_bytes())
+        .may_load()?
+        .unwrap_or(0))
+}
+
+// recounts the non-empty balances, for deployments whose counter was added after them
+pub fn rebuild_holder_count<S: Storage>(storage: &mut S) -> StdResult<u64> {
+    let mut count = 0u64;
+    let balances = ReadonlyPrefixedStorage::new(StorageKey::Balances.as_bytes(), &*storage);
+    for (_, value) in balances.range(None, None, Order::Ascending) {
+        let amount: Uint128 = from_slice(&value)?;
+        if amount.u128() != 0 {
+            count += 1;
+        }
     }
+    singleton(storage, StorageKey::HolderCount.as_bytes()).save(&count)?;
+    Ok(count)
 }
 
 pub struct ReadOnlyBalances<'a, S: Storage> {
-    storage: ReadonlyPrefixedStorage<'a, S>,
+    storage: &'a S,
 }
 
 impl<'a, S: Storage> ReadOnlyBalances<'a, S> {
     pub fn new(storage: &'a S) -> Self {
-        let storage = ReadonlyPrefixedStorage::new(BALANCES_KEY, storage);
         Self { storage }
     }
 
     pub fn get(&self, addr: &CanonicalAddr) -> StdResult<u128> {
-        ReadOnlyBalancesImpl(&self.storage).get(addr)
+        let balances = ReadonlyPrefixedStorage::new(StorageKey::Balances.as_bytes(), self.storage);
+        ReadOnlyBalancesImpl(&balances).get(addr)
+    }
+
+    pub fn exists(&self, addr: &CanonicalAddr) -> bool {
+        let balances = ReadonlyPrefixedStorage::new(StorageKey::Balances.as_bytes(), self.storage);
+        balances.get(addr.as_slice()).is_some()
+    }
+
+    pub fn get_at(&self, addr: &CanonicalAddr, checkpoint: u64) -> StdResult<u128> {
+        ensure_addr(addr)?;
+        let history = ReadonlyPrefixedStorage::multilevel(
+            &[StorageKey::BalanceHistory.as_bytes(), addr.as_slice()],
+            self.storage,
+        );
+        let recorded = history
+            .range(Some(&checkpoint.to_be_bytes()), None, Order::Ascending)
+            .next();
+        match recorded {
+            Some((_, value)) => {
+                let amount: Uint128 = from_slice(&value)?;
+                Ok(amount.u128())
+            }
+            None => self.get(addr),
+        }
+    }
+
+    pub fn range(
+        &self,
+        start_after: Option<&CanonicalAddr>,
+        limit: usize,
+    ) -> StdResult<Vec<(CanonicalAddr, u128)>> {
+        let balances = ReadonlyPrefixedStorage::new(StorageKey::Balances.as_bytes(), self.storage);
+        let start = start_after.map(|addr| [addr.as_slice(), &[0]].concat());
+        balances
+            .range(start.as_deref(), None, Order::Ascending)
+            .take(limit)
+            .map(|(key, value)| {
+                let amount: Uint128 = from_slice(&value)?;
+                Ok((CanonicalAddr::from(key), amount.u128()))
+            })
+            .collect()
     }
 }
 
@@ -68,6 +353,7 @@ struct ReadOnlyBalancesImpl<'a, S: ReadonlyStorage>(&'a S);
 
 impl<'a, S: ReadonlyStorage> ReadOnlyBalancesImpl<'a, S> {
     fn get(&self, addr: &CanonicalAddr) -> StdResult<u128> {
+        ensure_addr(addr)?;
         Ok(self
             .0
             .get(addr.as_slice())
@@ -79,23 +365,354 @@ impl<'a, S: ReadonlyStorage> ReadOnlyBalancesImpl<'a, S> {
     }
 }
 
-pub struct Allowances<'a, S: Storage> {
+pub struct Minters<'a, S: Storage> {
     storage: PrefixedStorage<'a, S>,
 }
 
+impl<'a, S: Storage> Minters<'a, S> {
+    pub fn new(storage: &'a mut S) -> Self {
+        let storage = PrefixedStorage::new(StorageKey::Minters.as_bytes(), storage);
+        Self { storage }
+    }
+
+    pub fn add(&mut self, addr: &CanonicalAddr) -> StdResult<()> {
+        self.storage.set(addr.as_slice(), &to_vec(&true)?);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, addr: &CanonicalAddr) {
+        self.storage.remove(addr.as_slice());
+    }
+
+    pub fn contains(&self, addr: &CanonicalAddr) -> bool {
+        ReadOnlyMintersImpl(&self.storage).contains(addr)
+    }
+}
+
+pub struct ReadOnlyMinters<'a, S: Storage> {
+    storage: ReadonlyPrefixedStorage<'a, S>,
+}
+
+impl<'a, S: Storage> ReadOnlyMinters<'a, S> {
+    pub fn new(storage: &'a S) -> Self {
+        let storage = ReadonlyPrefixedStorage::new(StorageKey::Minters.as_bytes(), storage);
+        Self { storage }
+    }
+
+    pub fn contains(&self, addr: &CanonicalAddr) -> bool {
+        ReadOnlyMintersImpl(&self.storage).contains(addr)
+    }
+
+    pub fn all(&self) -> Vec<CanonicalAddr> {
+        self.storage
+            .range(None, None, Order::Ascending)
+            .map(|(key, _)| CanonicalAddr::from(key))
+            .collect()
+    }
+}
+
+struct ReadOnlyMintersImpl<'a, S: ReadonlyStorage>(&'a S);
+
+impl<'a, S: ReadonlyStorage> ReadOnlyMintersImpl<'a, S> {
+    fn contains(&self, addr: &CanonicalAddr) -> bool {
+        self.0.get(addr.as_slice()).is_some()
+    }
+}
+
+pub struct FrozenAccounts<'a, S: Storage> {
+    storage: PrefixedStorage<'a, S>,
+}
+
+impl<'a, S: Storage> FrozenAccounts<'a, S> {
+    pub fn new(storage: &'a mut S) -> Self {
+        let storage = PrefixedStorage::new(StorageKey::Frozen.as_bytes(), storage);
+        Self { storage }
+    }
+
+    pub fn add(&mut self, addr: &CanonicalAddr) -> StdResult<()> {
+        self.storage.set(addr.as_slice(), &to_vec(&true)?);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, addr: &CanonicalAddr) {
+        self.storage.remove(addr.as_slice());
+    }
+}
+
+pub struct ReadOnlyFrozenAccounts<'a, S: Storage> {
+    storage: ReadonlyPrefixedStorage<'a, S>,
+}
+
+impl<'a, S: Storage> ReadOnlyFrozenAccounts<'a, S> {
+    pub fn new(storage: &'a S) -> Self {
+        let storage = ReadonlyPrefixedStorage::new(StorageKey::Frozen.as_bytes(), storage);
+        Self { storage }
+    }
+
+    pub fn contains(&self, addr: &CanonicalAddr) -> bool {
+        self.storage.get(addr.as_slice()).is_some()
+    }
+}
+
+// `cliff` and `duration` are seconds after `start`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct Vesting {
+    pub locked: Uint128,
+    pub start: u64,
+    pub cliff: u64,
+    pub duration: u64,
+}
+
+impl Vesting {
+    pub fn vested_at(&self, time: u64) -> u128 {
+        let locked = self.locked.u128();
+        let elapsed = time.saturating_sub(self.start);
+        if elapsed < self.cliff {
+            return 0;
+        }
+        if elapsed >= self.duration {
+            return locked;
+        }
+
+        // split the division so the multiply stays within u128
+        let (elapsed, duration) = (elapsed as u128, self.duration as u128);
+        locked / duration * elapsed + locked % duration * elapsed / duration
+    }
+
+    pub fn locked_at(&self, time: u64) -> u128 {
+        self.locked.u128() - self.vested_at(time)
+    }
+}
+
+pub struct Vestings<'a, S: Storage> {
+    storage: PrefixedStorage<'a, S>,
+}
+
+impl<'a, S: Storage> Vestings<'a, S> {
+    pub fn new(storage: &'a mut S) -> Self {
+        let storage = PrefixedStorage::new(StorageKey::Vesting.as_bytes(), storage);
+        Self { storage }
+    }
+
+    pub fn set(&mut self, addr: &CanonicalAddr, vesting: &Vesting) -> StdResult<()> {
+        ensure_addr(addr)?;
+        self.storage.set(addr.as_slice(), &to_vec(vesting)?);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, addr: &CanonicalAddr) -> StdResult<()> {
+        ensure_addr(addr)?;
+        self.storage.remove(addr.as_slice());
+        Ok(())
+    }
+}
+
+pub struct ReadOnlyVestings<'a, S: Storage> {
+    storage: ReadonlyPrefixedStorage<'a, S>,
+}
+
+impl<'a, S: Storage> ReadOnlyVestings<'a, S> {
+    pub fn new(storage: &'a S) -> Self {
+        let storage = ReadonlyPrefixedStorage::new(StorageKey::Vesting.as_bytes(), storage);
+        Self { storage }
+    }
+
+    pub fn get(&self, addr: &CanonicalAddr) -> StdResult<Option<Vesting>> {
+        ensure_addr(addr)?;
+        self.storage
+            .get(addr.as_slice())
+            .as_deref()
+            .map(from_slice)
+            .transpose()
+    }
+}
+
+// tokens moved out of an address during the day that began at `window_start`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Outflow {
+    pub window_start: u64,
+    pub spent: Uint128,
+}
+
+pub struct Outflows<'a, S: Storage> {
+    storage: PrefixedStorage<'a, S>,
+}
+
+impl<'a, S: Storage> Outflows<'a, S> {
+    pub fn new(storage: &'a mut S) -> Self {
+        let storage = PrefixedStorage::new(StorageKey::Outflows.as_bytes(), storage);
+        Self { storage }
+    }
+
+    pub fn set(&mut self, addr: &CanonicalAddr, outflow: &Outflow) -> StdResult<()> {
+        ensure_addr(addr)?;
+        self.storage.set(addr.as_slice(), &to_vec(outflow)?);
+        Ok(())
+    }
+
+    pub fn get(&self, addr: &CanonicalAddr) -> StdResult<Option<Outflow>> {
+        ensure_addr(addr)?;
+        self.storage
+            .get(addr.as_slice())
+            .as_deref()
+            .map(from_slice)
+            .transpose()
+    }
+}
+
+pub struct ReadOnlyOutflows<'a, S: Storage> {
+    storage: ReadonlyPrefixedStorage<'a, S>,
+}
+
+impl<'a, S: Storage> ReadOnlyOutflows<'a, S> {
+    pub fn new(storage: &'a S) -> Self {
+        let storage = ReadonlyPrefixedStorage::new(StorageKey::Outflows.as_bytes(), storage);
+        Self { storage }
+    }
+
+    pub fn get(&self, addr: &CanonicalAddr) -> StdResult<Option<Outflow>> {
+        ensure_addr(addr)?;
+        self.storage
+            .get(addr.as_slice())
+            .as_deref()
+            .map(from_slice)
+            .transpose()
+    }
+}
+
+// block height at which each address last received tokens through a transfer
+pub struct LastReceived<'a, S: Storage> {
+    storage: PrefixedStorage<'a, S>,
+}
+
+impl<'a, S: Storage> LastReceived<'a, S> {
+    pub fn new(storage: &'a mut S) -> Self {
+        let storage = PrefixedStorage::new(StorageKey::LastReceived.as_bytes(), storage);
+        Self { storage }
+    }
+
+    pub fn set(&mut self, addr: &CanonicalAddr, height: u64) -> StdResult<()> {
+        ensure_addr(addr)?;
+        self.storage.set(addr.as_slice(), &to_vec(&height)?);
+        Ok(())
+    }
+
+    pub fn get(&self, addr: &CanonicalAddr) -> StdResult<Option<u64>> {
+        ensure_addr(addr)?;
+        self.storage
+            .get(addr.as_slice())
+            .as_deref()
+            .map(from_slice)
+            .transpose()
+    }
+}
+
+pub struct ReadOnlyLastReceived<'a, S: Storage> {
+    storage: ReadonlyPrefixedStorage<'a, S>,
+}
+
+impl<'a, S: Storage> ReadOnlyLastReceived<'a, S> {
+    pub fn new(storage: &'a S) -> Self {
+        let storage = ReadonlyPrefixedStorage::new(StorageKey::LastReceived.as_bytes(), storage);
+        Self { storage }
+    }
+
+    pub fn get(&self, addr: &CanonicalAddr) -> StdResult<Option<u64>> {
+        ensure_addr(addr)?;
+        self.storage
+            .get(addr.as_slice())
+            .as_deref()
+            .map(from_slice)
+            .transpose()
+    }
+}
+
+pub struct MintNonces<'a, S: Storage> {
+    storage: PrefixedStorage<'a, S>,
+}
+
+impl<'a, S: Storage> MintNonces<'a, S> {
+    pub fn new(storage: &'a mut S) -> Self {
+        let storage = PrefixedStorage::new(StorageKey::MintNonces.as_bytes(), storage);
+        Self { storage }
+    }
+
+    pub fn add(&mut self, nonce: &str) -> StdResult<()> {
+        self.storage.set(nonce.as_bytes(), &to_vec(&true)?);
+        Ok(())
+    }
+}
+
+pub struct ReadOnlyMintNonces<'a, S: Storage> {
+    storage: ReadonlyPrefixedStorage<'a, S>,
+}
+
+impl<'a, S: Storage> ReadOnlyMintNonces<'a, S> {
+    pub fn new(storage: &'a S) -> Self {
+        let storage = ReadonlyPrefixedStorage::new(StorageKey::MintNonces.as_bytes(), storage);
+        Self { storage }
+    }
+
+    pub fn contains(&self, nonce: &str) -> bool {
+        self.storage.get(nonce.as_bytes()).is_some()
+    }
+}
+
+pub struct Allowances<'a, S: Storage> {
+    owner: CanonicalAddr,
+    storage: &'a mut S,
+}
+
 impl<'a, S: Storage> Allowances<'a, S> {
     pub fn new(owner: &CanonicalAddr, storage: &'a mut S) -> Self {
-        let storage = PrefixedStorage::multilevel(&[ALLOWANCES_KEY, owner.as_slice()], storage);
-        Self { storage }
+        let owner = owner.clone();
+        Self { owner, storage }
     }
 
+    pub fn for_owner<A: Api>(api: &A, owner: &HumanAddr, storage: &'a mut S) -> StdResult<Self> {
+        let owner = api.canonical_address(owner)?;
+        Ok(Self::new(&owner, storage))
+    }
+
+    pub fn owner(&self) -> &CanonicalAddr {
+        &self.owner
+    }
+
+    // every write also maintains the [spender_index, spender, owner] entry
     pub fn set(&mut self, addr: &CanonicalAddr, allowance: Allowance) -> StdResult<()> {
-        self.storage.set(addr.as_slice(), &to_vec(&allowance)?);
+        ensure_addr(&self.owner)?;
+        ensure_addr(addr)?;
+        let owner = self.owner.clone();
+        self.allowances().set(addr.as_slice(), &to_vec(&allowance)?);
+        self.spender_index(addr)
+            .set(owner.as_slice(), &to_vec(&true)?);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, addr: &CanonicalAddr) -> StdResult<()> {
+        ensure_addr(addr)?;
+        let owner = self.owner.clone();
+        self.allowances().remove(addr.as_slice());
+        self.spender_index(addr).remove(owner.as_slice());
         Ok(())
     }
 
     pub fn get(&self, addr: &CanonicalAddr) -> StdResult<Option<Allowance>> {
-        ReadOnlyAllowancesImpl(&self.storage).get(addr)
+        ReadOnlyAllowances::new(&self.owner, &*self.storage).get(addr)
+    }
+
+    fn allowances(&mut self) -> PrefixedStorage<S> {
+        PrefixedStorage::multilevel(
+            &[StorageKey::Allowances.as_bytes(), self.owner.as_slice()],
+            &mut *self.storage,
+        )
+    }
+
+    fn spender_index(&mut self, spender: &CanonicalAddr) -> PrefixedStorage<S> {
+        PrefixedStorage::multilevel(
+            &[StorageKey::SpenderIndex.as_bytes(), spender.as_slice()],
+            &mut *self.storage,
+        )
     }
 }
 
@@ -105,20 +722,59 @@ pub struct ReadOnlyAllowances<'a, S: Storage> {
 
 impl<'a, S: Storage> ReadOnlyAllowances<'a, S> {
     pub fn new(owner: &CanonicalAddr, storage: &'a S) -> Self {
-        let storage =
-            ReadonlyPrefixedStorage::multilevel(&[ALLOWANCES_KEY, owner.as_slice()], storage);
+        let storage = ReadonlyPrefixedStorage::multilevel(
+            &[StorageKey::Allowances.as_bytes(), owner.as_slice()],
+            storage,
+        );
         Self { storage }
     }
 
     pub fn get(&self, addr: &CanonicalAddr) -> StdResult<Option<Allowance>> {
         ReadOnlyAllowancesImpl(&self.storage).get(addr)
     }
+
+    pub fn range(
+        &self,
+        start_after: Option<&CanonicalAddr>,
+        limit: usize,
+    ) -> StdResult<Vec<(CanonicalAddr, Allowance)>> {
+        let start = start_after.map(|addr| [addr.as_slice(), &[0]].concat());
+        self.storage
+            .range(start.as_deref(), None, Order::Ascending)
+            .take(limit)
+            .map(|(key, value)| Ok((CanonicalAddr::from(key), from_slice(&value)?)))
+            .collect()
+    }
+}
+
+pub struct ReadOnlySpenderIndex<'a, S: Storage> {
+    storage: ReadonlyPrefixedStorage<'a, S>,
+}
+
+impl<'a, S: Storage> ReadOnlySpenderIndex<'a, S> {
+    pub fn new(spender: &CanonicalAddr, storage: &'a S) -> Self {
+        let storage = ReadonlyPrefixedStorage::multilevel(
+            &[StorageKey::SpenderIndex.as_bytes(), spender.as_slice()],
+            storage,
+        );
+        Self { storage }
+    }
+
+    pub fn owners(&self, start_after: Option<&CanonicalAddr>, limit: usize) -> Vec<CanonicalAddr> {
+        let start = start_after.map(|addr| [addr.as_slice(), &[0]].concat());
+        self.storage
+            .range(start.as_deref(), None, Order::Ascending)
+            .take(limit)
+            .map(|(key, _)| CanonicalAddr::from(key))
+            .collect()
+    }
 }
 
 struct ReadOnlyAllowancesImpl<'a, S: ReadonlyStorage>(&'a S);
 
 impl<'a, S: ReadonlyStorage> ReadOnlyAllowancesImpl<'a, S> {
     fn get(&self, addr: &CanonicalAddr) -> StdResult<Option<Allowance>> {
+        ensure_addr(addr)?;
         self.0
             .get(addr.as_slice())
             .as_deref()
@@ -127,8 +783,359 @@ impl<'a, S: ReadonlyStorage> ReadOnlyAllowancesImpl<'a, S> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// drops the zero balances the original release left behind, so ranging over balances
+// only yields holders, and returns how many were dropped
+pub fn prune_empty_balances<S: Storage>(storage: &mut S) -> StdResult<u64> {
+    let empty: Vec<_> = ReadonlyPrefixedStorage::new(StorageKey::Balances.as_bytes(), storage)
+        .range(None, None, Order::Ascending)
+        .map(|(key, value)| Ok((key, from_slice::<Uint128>(&value)?)))
+        .collect::<StdResult<Vec<_>>>()?
+        .into_iter()
+        .filter(|(_, amount)| amount.u128() == 0)
+        .map(|(key, _)| key)
+        .collect();
+
+    let mut balances = PrefixedStorage::new(StorageKey::Balances.as_bytes(), storage);
+    for key in &empty {
+        balances.remove(key);
+    }
+    Ok(empty.len() as u64)
+}
+
+// rewrites every stored allowance in the current shape and gives it its spender index
+// entry, for deployments whose allowances predate the index, returning how many there were
+pub fn rebuild_spender_index<S: Storage>(storage: &mut S) -> StdResult<u64> {
+    let entries: Vec<_> = ReadonlyPrefixedStorage::new(StorageKey::Allowances.as_bytes(), storage)
+        .range(None, None, Order::Ascending)
+        .collect();
+
+    for (key, value) in &entries {
+        // multilevel keys carry the owner as a big-endian u16 length followed by its bytes
+        let (owner, spender) = match key.as_slice() {
+            [high, low, rest @ ..] => {
+                let len = usize::from(u16::from_be_bytes([*high, *low]));
+                if rest.len() <= len {
+                    return Err(StdError::generic_err("Malformed allowance key"));
+                }
+                rest.split_at(len)
+            }
+            _ => return Err(StdError::generic_err("Malformed allowance key")),
+        };
+        let allowance: Allowance = from_slice(value)?;
+
+        let owner = CanonicalAddr::from(owner);
+        Allowances::new(&owner, storage).set(&CanonicalAddr::from(spender), allowance)?;
+    }
+    Ok(entries.len() as u64)
+}
+
+// an empty key would collapse every such address onto one shared entry
+fn ensure_addr(addr: &CanonicalAddr) -> StdResult<()> {
+    if addr.is_empty() {
+        return Err(StdError::generic_err("Canonical address must not be empty"));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct Allowance {
     pub is_allowed: bool,
     pub amount: Uint128,
+    #[serde(default)]
+    pub expires: Expiration,
+    // recurring allowances refill `amount` to `per_period_limit` every `period` seconds
+    #[serde(default)]
+    pub period: Option<u64>,
+    #[serde(default)]
+    pub period_reset_at: Option<u64>,
+    #[serde(default)]
+    pub per_period_limit: Option<Uint128>,
+    // set by approving `u128::MAX`; spends leave the amount untouched
+    #[serde(default)]
+    pub unlimited: bool,
+}
+
+impl Allowance {
+    pub fn refresh(&mut self, block: &BlockInfo) {
+        let (period, reset_at, limit) =
+            match (self.period, self.period_reset_at, self.per_period_limit) {
+                (Some(period), Some(reset_at), Some(limit)) if period > 0 => {
+                    (period, reset_at, limit)
+                }
+                _ => return,
+            };
+        if block.time < reset_at {
+            return;
+        }
+
+        let elapsed_periods = (block.time - reset_at) / period + 1;
+        self.amount = limit;
+        self.period_reset_at =
+            Some(reset_at.saturating_add(elapsed_periods.saturating_mul(period)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn balances_range() {
+        let mut storage = MockStorage::new();
+
+        let first = CanonicalAddr::from(b"first".to_vec());
+        let second = CanonicalAddr::from(b"second".to_vec());
+        let third = CanonicalAddr::from(b"third".to_vec());
+
+        let mut balances = Balances::new(&mut storage);
+        balances.set(&second, 200).unwrap();
+        balances.set(&third, 300).unwrap();
+        balances.set(&first, 100).unwrap();
+
+        let balances = ReadOnlyBalances::new(&storage);
+        assert_eq!(
+            balances.range(None, 2).unwrap(),
+            vec![(first.clone(), 100), (second.clone(), 200)]
+        );
+        assert_eq!(
+            balances.range(Some(&second), 2).unwrap(),
+            vec![(third, 300)]
+        );
+    }
+
+    #[test]
+    fn holder_count_out_of_sync() {
+        let mut storage = MockStorage::new();
+
+        let first = CanonicalAddr::from(b"first".to_vec());
+        let second = CanonicalAddr::from(b"second".to_vec());
+
+        let mut balances = Balances::new(&mut storage);
+        balances.set(&first, 100).unwrap();
+        balances.set(&second, 200).unwrap();
+        assert_eq!(holder_count(&storage).unwrap(), 2);
+
+        // a deployment whose balances predate the counter
+        singleton::<_, u64>(&mut storage, StorageKey::HolderCount.as_bytes()).remove();
+        assert_eq!(holder_count(&storage).unwrap(), 0);
+
+        let err = Balances::new(&mut storage).set(&first, 0).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Holder count is out of sync, migrate to rebuild it")
+        );
+
+        assert_eq!(rebuild_holder_count(&mut storage).unwrap(), 2);
+        Balances::new(&mut storage).set(&first, 0).unwrap();
+        assert_eq!(holder_count(&storage).unwrap(), 1);
+    }
+
+    #[test]
+    fn balances_range_skips_emptied() {
+        let mut storage = MockStorage::new();
+
+        let first = CanonicalAddr::from(b"first".to_vec());
+        let second = CanonicalAddr::from(b"second".to_vec());
+
+        let mut balances = Balances::new(&mut storage);
+        balances.set(&first, 100).unwrap();
+        balances.set(&second, 200).unwrap();
+        balances.set(&first, 0).unwrap();
+
+        let balances = ReadOnlyBalances::new(&storage);
+        assert_eq!(balances.range(None, 10).unwrap(), vec![(second, 200)]);
+    }
+
+    #[test]
+    fn allowances_range() {
+        let mut storage = MockStorage::new();
+
+        let owner = CanonicalAddr::from(b"owner".to_vec());
+        let first = CanonicalAddr::from(b"first".to_vec());
+        let second = CanonicalAddr::from(b"second".to_vec());
+
+        let mut allowances = Allowances::new(&owner, &mut storage);
+        allowances
+            .set(
+                &second,
+                Allowance {
+                    is_allowed: false,
+                    amount: Uint128(200),
+                    expires: Expiration::Never,
+                    ..Allowance::default()
+                },
+            )
+            .unwrap();
+        allowances
+            .set(
+                &first,
+                Allowance {
+                    is_allowed: true,
+                    amount: Uint128(100),
+                    expires: Expiration::Never,
+                    ..Allowance::default()
+                },
+            )
+            .unwrap();
+
+        let allowances = ReadOnlyAllowances::new(&owner, &storage);
+        assert_eq!(
+            allowances.range(None, 10).unwrap(),
+            vec![
+                (
+                    first.clone(),
+                    Allowance {
+                        is_allowed: true,
+                        amount: Uint128(100),
+                        expires: Expiration::Never,
+                        ..Allowance::default()
+                    }
+                ),
+                (
+                    second.clone(),
+                    Allowance {
+                        is_allowed: false,
+                        amount: Uint128(200),
+                        expires: Expiration::Never,
+                        ..Allowance::default()
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            allowances.range(Some(&first), 10).unwrap(),
+            vec![(
+                second,
+                Allowance {
+                    is_allowed: false,
+                    amount: Uint128(200),
+                    expires: Expiration::Never,
+                    ..Allowance::default()
+                }
+            )]
+        );
+
+        let other = CanonicalAddr::from(b"other".to_vec());
+        assert!(ReadOnlyAllowances::new(&other, &storage)
+            .range(None, 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn allowance_round_trip() {
+        let allowance = Allowance {
+            is_allowed: true,
+            amount: Uint128(42),
+            expires: Expiration::Never,
+            ..Allowance::default()
+        };
+
+        let bytes = to_vec(&allowance).unwrap();
+        let decoded: Allowance = from_slice(&bytes).unwrap();
+        assert_eq!(decoded, allowance);
+        assert_eq!(decoded.clone(), allowance);
+    }
+
+    #[test]
+    fn empty_address_rejected() {
+        let mut storage = MockStorage::new();
+        let empty = CanonicalAddr::from(vec![]);
+        let err = || StdError::generic_err("Canonical address must not be empty");
+
+        let mut balances = Balances::new(&mut storage);
+        assert_eq!(balances.set(&empty, 100), Err(err()));
+        assert_eq!(balances.get(&empty), Err(err()));
+        assert!(ReadOnlyBalances::new(&storage)
+            .range(None, 10)
+            .unwrap()
+            .is_empty());
+
+        let owner = CanonicalAddr::from(b"owner".to_vec());
+        let mut allowances = Allowances::new(&owner, &mut storage);
+        let allowance = Allowance {
+            is_allowed: true,
+            amount: Uint128(1),
+            expires: Expiration::Never,
+            ..Allowance::default()
+        };
+        assert_eq!(allowances.set(&empty, allowance.clone()), Err(err()));
+        assert_eq!(allowances.get(&empty), Err(err()));
+
+        let mut allowances = Allowances::new(&empty, &mut storage);
+        let spender = CanonicalAddr::from(b"spender".to_vec());
+        assert_eq!(allowances.set(&spender, allowance), Err(err()));
+    }
+
+    #[test]
+    fn storage_keys_do_not_alias() {
+        for (i, a) in StorageKey::ALL.iter().enumerate() {
+            for b in &StorageKey::ALL[i + 1..] {
+                assert!(
+                    !a.as_bytes().starts_with(b.as_bytes())
+                        && !b.as_bytes().starts_with(a.as_bytes()),
+                    "{:?} and {:?} share a prefix",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn allowance_schema_and_serialization() {
+        use cosmwasm_schema::schema_for;
+
+        for schema in &[
+            schema_for!(State),
+            schema_for!(Allowance),
+            schema_for!(Vesting),
+        ] {
+            assert!(!to_vec(schema).unwrap().is_empty());
+        }
+
+        let allowance = Allowance {
+            is_allowed: true,
+            amount: Uint128(100),
+            expires: Expiration::AtHeight(50),
+            period: Some(10),
+            period_reset_at: Some(20),
+            per_period_limit: Some(Uint128(100)),
+            unlimited: false,
+        };
+        let json = to_vec(&allowance).unwrap();
+        assert_eq!(
+            String::from_utf8(json.clone()).unwrap(),
+            r#"{"is_allowed":true,"amount":"100","expires":{"at_height":50},"period":10,"period_reset_at":20,"per_period_limit":"100","unlimited":false}"#
+        );
+        assert_eq!(from_slice::<Allowance>(&json).unwrap(), allowance);
+    }
+
+    #[test]
+    fn state_without_later_fields() {
+        // a State as stored before the fee rounding, holder burn, burn address and burn
+        // fee settings existed
+        let stored = br#"{
+            "name": "Craboken",
+            "symbol": "CRAB",
+            "decimals": 6,
+            "total_supply": "1000",
+            "cap": null,
+            "paused": false,
+            "fee_bps": 25,
+            "fee_collector": "collector",
+            "admin": "admin",
+            "max_mint_per_tx": null,
+            "min_transfer_amount": null,
+            "default_allowance_ttl": null,
+            "daily_transfer_limit": null
+        }"#;
+        let state: State = from_slice(stored).unwrap();
+        assert_eq!(state.fee_rounding, Rounding::Down);
+        assert!(state.burnable_by_holders);
+        assert!(state.burn_address.is_empty());
+        assert_eq!(state.cooldown_blocks, None);
+        assert_eq!(state.burn_fee, Uint128(0));
+    }
 }